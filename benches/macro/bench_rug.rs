@@ -25,7 +25,7 @@ impl Bench for DifficultCompositeRug {
         }
         let r_bak = r.clone();
         let t = std::time::SystemTime::now();
-        r.factor_events(crate::util::DottingEventSubscriptor());
+        r.factor_events(&mut crate::util::DottingEventSubscriptor());
         let d = t.elapsed().unwrap();
         println!();
         vec![BenchmarkElement::new(&r_bak, &d)]
@@ -54,7 +54,7 @@ impl Bench for ManyCompositesRug {
         }
         let r_bak = r.clone();
         let t = std::time::SystemTime::now();
-        r.factor_events(crate::util::DottingEventSubscriptor());
+        r.factor_events(&mut crate::util::DottingEventSubscriptor());
         let d = t.elapsed().unwrap();
         println!();
         vec![BenchmarkElement::new(&r_bak, &d)]
@@ -0,0 +1,415 @@
+#![allow(clippy::many_single_char_names)]
+
+use std::collections::BTreeMap;
+
+use super::order::crt;
+use super::{checked_factor_exponents, from_u32, ArithmeticInteger, MultiplicativeOrder};
+use crate::factoring::{find_cycle, CycleConditionChecker, MapFunction, NoIterationLimit};
+use crate::util::power;
+use crate::Factorization;
+
+/// Prime subgroups of at most this order are solved by baby-step giant-step, larger ones by pollard's rho
+const BSGS_THRESHOLD: u32 = 1 << 24;
+
+/// Number of starting points tried by pollard's rho and the kangaroo method before giving up
+const ATTEMPTS: u8 = 32;
+
+/// Discrete logarithms in the units modulo a number
+///
+/// # Example
+/// ```
+/// use facto::arithmetic::DiscreteLogarithm;
+/// // 3^x ≡ 13 (mod 17)
+/// assert_eq!(3u64.discrete_log(13, 17), Some(4));
+/// // 2 only generates the squares modulo 7
+/// assert_eq!(2u64.discrete_log(3, 7), None);
+/// assert_eq!(3u64.discrete_log_interval(3u64.pow(30) % 1019, 1019, 20, 40), Some(30));
+/// ```
+pub trait DiscreteLogarithm: MultiplicativeOrder {
+    #[must_use]
+    /// Smallest x ≥ 0 with `self`^x ≡ `h` (mod `n`), using Pohlig–Hellman on the factorization of λ(n)
+    ///
+    /// # Returns
+    /// `None` if `self` is not coprime to `n`, or `h` is not a power of `self`
+    ///
+    /// # Panics
+    /// If `n` is zero
+    fn discrete_log(self, h: Self, n: Self) -> Option<Self> {
+        let lambda = checked_factor_exponents(n.clone()).carmichael_lambda_factorization();
+        self.discrete_log_with(h, &n, &lambda)
+    }
+
+    #[must_use]
+    /// Smallest x ≥ 0 with `self`^x ≡ `h` (mod `n`), given the factorization of a multiple of the order of `self`
+    ///
+    /// The multiple can be λ(n), see [`Factorization::carmichael_lambda_factorization`], or the order of a known subgroup.
+    /// Every prime power subgroup is solved separately, by baby-step giant-step for small primes and pollard's rho otherwise.
+    ///
+    /// # Returns
+    /// `None` if `self` is not coprime to `n`, or `h` is not a power of `self`
+    fn discrete_log_with(self, h: Self, n: &Self, multiple: &Factorization<Self>) -> Option<Self> {
+        let one = Self::from(1) % n.clone();
+        let g = self % n.clone();
+        let h = h % n.clone();
+        let order = g.clone().multiplicative_order_with(n, multiple)?;
+        if h.power_mod(&order, n) != one {
+            return None;
+        }
+        let order_factors = Factorization::from_factors(
+            multiple
+                .factors()
+                .iter()
+                .map(|(p, _)| (p.clone(), multiplicity(&order, p)))
+                .collect(),
+        );
+        let mut result = Self::from(0);
+        let mut modulus = Self::from(1);
+        for (p, e) in order_factors.factors() {
+            let prime_power = power(p.clone(), *e);
+            let cofactor = order.clone() / prime_power.clone();
+            let residue = prime_power_log(
+                &g.power_mod(&cofactor, n),
+                &h.power_mod(&cofactor, n),
+                p,
+                *e,
+                n,
+            )?;
+            result = crt(&result, &modulus, &residue, p, *e);
+            modulus = modulus * prime_power;
+        }
+        Some(result)
+    }
+
+    #[must_use]
+    /// Some x with `lower` ≤ x ≤ `upper` and `self`^x ≡ `h` (mod `n`), using pollard's kangaroo method
+    ///
+    /// Takes about the square root of the interval width steps and constant memory, independent of the group order.
+    /// As the method is probabilistic, a logarithm in the interval is missed with a very small probability.
+    ///
+    /// # Returns
+    /// `None` if no logarithm in the interval has been found
+    fn discrete_log_interval(self, h: Self, n: Self, lower: Self, upper: Self) -> Option<Self> {
+        if lower > upper {
+            return None;
+        }
+        let g = self % n.clone();
+        let h = h % n.clone();
+        let width = upper.clone() - lower;
+        let m = integer_square_root(&width) + Self::from(1);
+        // Jumps are powers of two, their mean should be about half the square root of the width
+        let mut jump_count = 1;
+        while (power(Self::from(2), jump_count) - Self::from(1))
+            < m.clone() / Self::from(2) * from_u32(jump_count)
+        {
+            jump_count += 1;
+        }
+        let jumps: Vec<(Self, Self)> = (0..jump_count)
+            .map(|i| {
+                let distance = power(Self::from(2), i);
+                (g.power_mod(&distance, &n), distance)
+            })
+            .collect();
+        let tame_steps = m * Self::from(2);
+        for attempt in 0..ATTEMPTS {
+            let jump = |x: &Self| {
+                let index = small_value((x.clone() + Self::from(attempt)) % from_u32(jump_count));
+                &jumps[usize::from(index)]
+            };
+            // No distance exceeds the width, so that nothing overflows near the maximum of the type
+            let mut tame = g.power_mod(&upper, &n);
+            let mut tame_distance = Self::from(0);
+            let mut step = Self::from(0);
+            while step < tame_steps {
+                let (factor, distance) = jump(&tame);
+                if *distance > width.clone() - tame_distance.clone() {
+                    break;
+                }
+                tame = tame.mul_mod(factor, &n);
+                tame_distance = tame_distance + distance.clone();
+                step = step + Self::from(1);
+            }
+            // The wild kangaroo starts `behind` the tame one, once it passed it the distance `ahead` is tracked instead
+            let mut wild = h.clone();
+            let mut behind = tame_distance;
+            let mut ahead = None;
+            loop {
+                if wild == tame {
+                    match ahead {
+                        Some(ahead) => return Some(upper - ahead),
+                        None if behind == Self::from(0) => return Some(upper),
+                        None => break,
+                    }
+                }
+                let (factor, distance) = jump(&wild);
+                ahead = match ahead {
+                    Some(ahead) if *distance > width.clone() - ahead.clone() => break,
+                    Some(ahead) => Some(ahead + distance.clone()),
+                    None if *distance < behind => {
+                        behind = behind - distance.clone();
+                        None
+                    }
+                    None => Some(distance.clone() - behind.clone()),
+                };
+                wild = wild.mul_mod(factor, &n);
+            }
+        }
+        None
+    }
+}
+
+impl<T: MultiplicativeOrder> DiscreteLogarithm for T {}
+
+/// Exponent of `p` in `n`
+fn multiplicity<T: ArithmeticInteger>(n: &T, p: &T) -> u32 {
+    let mut n = n.clone();
+    let mut result = 0;
+    while n.clone() % p.clone() == T::from(0) {
+        n = n / p.clone();
+        result += 1;
+    }
+    result
+}
+
+/// Convert a number below 256 to `u8`
+fn small_value<T: ArithmeticInteger>(mut x: T) -> u8 {
+    let mut result = 0;
+    for bit in (0..8).rev() {
+        let value = T::from(1u8 << bit);
+        if x >= value {
+            x = x - value;
+            result |= 1 << bit;
+        }
+    }
+    result
+}
+
+fn integer_square_root<T: ArithmeticInteger>(n: &T) -> T {
+    if *n < T::from(2) {
+        return n.clone();
+    }
+    let mut x = n.clone();
+    let mut y = (x.clone() + T::from(1)) / T::from(2);
+    while y < x {
+        x = y;
+        y = (x.clone() + n.clone() / x.clone()) / T::from(2);
+    }
+    x
+}
+
+/// Logarithm of `h` to the base `g` of order `p^e` modulo `n`, digit by digit in base `p`
+fn prime_power_log<T: MultiplicativeOrder>(g: &T, h: &T, p: &T, e: u32, n: &T) -> Option<T> {
+    let prime_power = power(p.clone(), e);
+    let generator = g.power_mod(&power(p.clone(), e - 1), n);
+    let mut result = T::from(0);
+    let mut digit_value = T::from(1);
+    for k in 0..e {
+        // Remove the known digits, then project into the subgroup of order p
+        let inverse_power = g.power_mod(&(prime_power.clone() - result.clone()), n);
+        let reduced = h
+            .mul_mod(&inverse_power, n)
+            .power_mod(&power(p.clone(), e - 1 - k), n);
+        let digit = prime_log(&generator, &reduced, p, n)?;
+        result = result + digit * digit_value.clone();
+        digit_value = digit_value * p.clone();
+    }
+    Some(result)
+}
+
+/// Logarithm of `h` to the base `g` of prime order `p` modulo `n`
+fn prime_log<T: MultiplicativeOrder>(g: &T, h: &T, p: &T, n: &T) -> Option<T> {
+    if *h == T::from(1) % n.clone() {
+        return Some(T::from(0));
+    }
+    if *p <= from_u32(BSGS_THRESHOLD) {
+        baby_step_giant_step(g, h, p, n)
+    } else {
+        rho_log(g, h, p, n)
+    }
+}
+
+fn baby_step_giant_step<T: MultiplicativeOrder>(g: &T, h: &T, p: &T, n: &T) -> Option<T> {
+    let m = integer_square_root(p) + T::from(1);
+    let mut baby_steps = BTreeMap::new();
+    let mut x = T::from(1) % n.clone();
+    let mut j = T::from(0);
+    while j < m {
+        baby_steps.entry(x.clone()).or_insert_with(|| j.clone());
+        x = x.mul_mod(g, n);
+        j = j + T::from(1);
+    }
+    let giant_step = g.power_mod(&(p.clone() - m.clone() % p.clone()), n);
+    let mut y = h.clone();
+    let mut i = T::from(0);
+    while i < m {
+        if let Some(j) = baby_steps.get(&y) {
+            return Some((i * m + j.clone()) % p.clone());
+        }
+        y = y.mul_mod(&giant_step, n);
+        i = i + T::from(1);
+    }
+    None
+}
+
+/// Element `g^a * h^b` of the walk, as the tuple `(g^a * h^b, a, b)`
+type RhoState<T> = (T, T, T);
+
+struct RhoLogMapper<'a, T> {
+    g: &'a T,
+    h: &'a T,
+    p: &'a T,
+    n: &'a T,
+}
+
+impl<T: MultiplicativeOrder> MapFunction<RhoState<T>> for RhoLogMapper<'_, T> {
+    fn run(&mut self, (x, a, b): RhoState<T>) -> RhoState<T> {
+        let class = x.clone() % T::from(3);
+        if class == T::from(0) {
+            (
+                x.mul_mod(self.h, self.n),
+                a,
+                (b + T::from(1)) % self.p.clone(),
+            )
+        } else if class == T::from(1) {
+            (
+                x.mul_mod(&x, self.n),
+                a * T::from(2) % self.p.clone(),
+                b * T::from(2) % self.p.clone(),
+            )
+        } else {
+            (
+                x.mul_mod(self.g, self.n),
+                (a + T::from(1)) % self.p.clone(),
+                b,
+            )
+        }
+    }
+}
+
+/// Remembers the two walk states with the same element
+struct RhoLogCollision<T> {
+    collision: Option<(RhoState<T>, RhoState<T>)>,
+}
+
+impl<T: PartialEq + Clone> CycleConditionChecker<RhoState<T>, u64> for RhoLogCollision<T> {
+    fn check(&mut self, tortoise: &RhoState<T>, hare: &RhoState<T>, _: &u64, _: &u64) -> bool {
+        if tortoise.0 == hare.0 {
+            self.collision = Some((tortoise.clone(), hare.clone()));
+            return true;
+        }
+        false
+    }
+}
+
+/// Pollard's rho for logarithms, finding a collision `g^a1 * h^b1 = g^a2 * h^b2` with brent's cycle detection
+fn rho_log<T: MultiplicativeOrder>(g: &T, h: &T, p: &T, n: &T) -> Option<T> {
+    for attempt in 1..=ATTEMPTS {
+        let a = T::from(attempt) % p.clone();
+        let start = (g.power_mod(&a, n).mul_mod(h, n), a, T::from(1));
+        let mapper = RhoLogMapper { g, h, p, n };
+        let checker = RhoLogCollision { collision: None };
+        let (checker, _) = find_cycle(mapper, checker, start, &mut NoIterationLimit)?;
+        let Some(((_, a1, b1), (_, a2, b2))) = checker.collision else {
+            continue;
+        };
+        // a1 + b1 x ≡ a2 + b2 x (mod p)
+        let denominator = (b1 + p.clone() - b2) % p.clone();
+        if denominator == T::from(0) {
+            continue;
+        }
+        let numerator = (a2 + p.clone() - a1) % p.clone();
+        let inverse = denominator.power_mod(&(p.clone() - T::from(2)), p);
+        let x = numerator.mul_mod(&inverse, p);
+        if g.power_mod(&x, n) == *h {
+            return Some(x);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DiscreteLogarithm;
+    use crate::arithmetic::MultiplicativeOrder;
+    use crate::Integer;
+
+    #[test]
+    fn small_numbers() {
+        for n in 1..60u64 {
+            for g in 0..n {
+                let mut powers = vec![];
+                let mut x = 1 % n;
+                while !powers.contains(&x) {
+                    powers.push(x);
+                    x = x * g % n;
+                }
+                for h in 0..n {
+                    let expected = if g.multiplicative_order(n).is_some() {
+                        powers.iter().position(|&y| y == h).map(|x| x as u64)
+                    } else {
+                        None
+                    };
+                    assert_eq!(g.discrete_log(h, n), expected, "log_{g} {h} mod {n}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rho() {
+        // p - 1 = 2 * 3 * 5 * 7 * 1073741843, the largest subgroup is solved by pollard's rho
+        let p: u64 = 2 * 3 * 5 * 7 * 1_073_741_843 + 1;
+        let g = p.primitive_root().unwrap();
+        for x in [0, 1, 123_456_789_012, p - 2] {
+            let h = g.power_mod(&x, &p);
+            assert_eq!(g.discrete_log(h, p), Some(x));
+            assert_eq!(
+                u128::from(g).discrete_log(u128::from(h), u128::from(p)),
+                Some(u128::from(x))
+            );
+        }
+    }
+
+    #[test]
+    fn prime_powers() {
+        // 3^20 * 2^10 divides the group order
+        let n = 3u64.pow(21) * 11 * 2u64.pow(12);
+        let g = 5;
+        let x = 1_234_567;
+        let h = g.power_mod(&x, &n);
+        let log = g.discrete_log(h, n).unwrap();
+        assert_eq!(g.power_mod(&log, &n), h);
+        assert_eq!(x % g.multiplicative_order(n).unwrap(), log);
+    }
+
+    #[test]
+    fn rug() {
+        let p = (Integer::from(1) << 127u32) - 1u32;
+        let g = p.clone().primitive_root().unwrap();
+        let x = Integer::from(0xdead_beef_cafe_babe_u64) << 40u32;
+        let h = g.power_mod(&x, &p);
+        assert_eq!(g.discrete_log(h, p), Some(x));
+    }
+
+    #[test]
+    fn kangaroo() {
+        let p = (1u64 << 61) - 1;
+        let g = 37;
+        let lower = 1_000_000_000;
+        let upper = lower + (1 << 24);
+        for x in [lower, lower + 12_345, upper] {
+            let h = g.power_mod(&x, &p);
+            assert_eq!(g.discrete_log_interval(h, p, lower, upper), Some(x));
+        }
+        assert_eq!(
+            g.discrete_log_interval(g.power_mod(&5, &p), p, lower, upper),
+            None
+        );
+        assert_eq!(g.discrete_log_interval(g, p, 1, 1), Some(1));
+        let x = u64::MAX - 500;
+        let h = g.power_mod(&x, &p);
+        assert_eq!(
+            g.discrete_log_interval(h, p, u64::MAX - 1000, u64::MAX),
+            Some(x)
+        );
+    }
+}
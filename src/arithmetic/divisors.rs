@@ -0,0 +1,249 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+use super::ArithmeticInteger;
+use crate::util::power;
+use crate::Factorization;
+
+impl<T: ArithmeticInteger> Factorization<T> {
+    #[must_use]
+    /// All divisors, including 1 and the number itself, in no particular order
+    ///
+    /// The divisors are generated one at a time, the memory needed only grows with the number of distinct primes.
+    ///
+    /// # Example
+    /// ```
+    /// use facto::{Factoring, Factorization};
+    /// let f = 12u64.factor_exponents();
+    /// assert_eq!(f.divisors().sorted().collect::<Vec<_>>(), [1, 2, 3, 4, 6, 12]);
+    /// assert_eq!(f.divisors().bounded(4).count(), 4);
+    /// assert_eq!(f.unitary_divisors().sorted().collect::<Vec<_>>(), [1, 3, 4, 12]);
+    /// ```
+    pub fn divisors(&self) -> Divisors<T> {
+        Divisors::new(
+            self.factors()
+                .iter()
+                .map(|(p, e)| (p.clone(), *e))
+                .collect(),
+        )
+    }
+
+    #[must_use]
+    /// Unitary divisors, i.e. divisors d for which d and n / d are coprime, in no particular order
+    pub fn unitary_divisors(&self) -> Divisors<T> {
+        Divisors::new(
+            self.factors()
+                .iter()
+                .map(|(p, e)| (power(p.clone(), *e), 1))
+                .collect(),
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Iterator over divisors, created by [`Factorization::divisors`] or [`Factorization::unitary_divisors`]
+///
+/// Each divisor is a product of `multiplier^count` over all steps, with `count` ranging up to the maximum count of the step.
+/// The counts are enumerated like an odometer, keeping the partial products of the higher steps.
+pub struct Divisors<T> {
+    /// Pairs of multiplier and maximum count
+    steps: Vec<(T, u32)>,
+    counts: Vec<u32>,
+    /// `products[i]` is the product of the multipliers of steps `i..`, raised to their counts
+    products: Vec<T>,
+    bound: Option<T>,
+    started: bool,
+    finished: bool,
+}
+
+impl<T: ArithmeticInteger> Divisors<T> {
+    fn new(steps: Vec<(T, u32)>) -> Self {
+        let len = steps.len();
+        Self {
+            steps,
+            counts: vec![0; len],
+            products: vec![T::from(1); len],
+            bound: None,
+            started: false,
+            finished: false,
+        }
+    }
+
+    #[must_use]
+    /// Only produce divisors less than or equal to `bound`
+    ///
+    /// Divisors exceeding the bound are pruned together with all their multiples, instead of being filtered.
+    pub fn bounded(mut self, bound: T) -> Self {
+        self.bound = Some(bound);
+        self
+    }
+
+    #[must_use]
+    /// Produce the divisors in ascending order, starting over from 1
+    pub fn sorted(self) -> SortedDivisors<T> {
+        let mut heap = BinaryHeap::new();
+        if self.within_bound(&T::from(1)) {
+            heap.push(Reverse(Candidate {
+                value: T::from(1),
+                counts: vec![0; self.steps.len()],
+                limit: self.steps.len(),
+            }));
+        }
+        SortedDivisors {
+            steps: self.steps,
+            bound: self.bound,
+            heap,
+        }
+    }
+
+    fn within_bound(&self, x: &T) -> bool {
+        self.bound.as_ref().is_none_or(|b| x <= b)
+    }
+}
+
+impl<T: ArithmeticInteger> Iterator for Divisors<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.finished {
+            return None;
+        }
+        if !self.started {
+            self.started = true;
+            if self.within_bound(&T::from(1)) {
+                return Some(T::from(1));
+            }
+            self.finished = true;
+            return None;
+        }
+        for i in 0..self.steps.len() {
+            let (multiplier, max_count) = &self.steps[i];
+            if self.counts[i] == *max_count {
+                continue;
+            }
+            let candidate = self.products[i].clone() * multiplier.clone();
+            if !self.within_bound(&candidate) {
+                continue;
+            }
+            self.counts[i] += 1;
+            for j in 0..=i {
+                self.products[j] = candidate.clone();
+            }
+            for count in &mut self.counts[..i] {
+                *count = 0;
+            }
+            return Some(candidate);
+        }
+        self.finished = true;
+        None
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Divisor waiting in the heap of [`SortedDivisors`]
+struct Candidate<T> {
+    value: T,
+    counts: Vec<u32>,
+    /// Steps `..limit` may be applied to get the successors of this divisor
+    limit: usize,
+}
+
+impl<T: Ord> PartialEq for Candidate<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<T: Ord> Eq for Candidate<T> {}
+
+impl<T: Ord> PartialOrd for Candidate<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Ord> Ord for Candidate<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.value.cmp(&other.value)
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Iterator over divisors in ascending order, created by [`Divisors::sorted`]
+///
+/// Every divisor except 1 is generated exactly once from the divisor with one less factor of its first step,
+/// so the heap only holds the successors of the divisors produced so far.
+pub struct SortedDivisors<T> {
+    steps: Vec<(T, u32)>,
+    bound: Option<T>,
+    heap: BinaryHeap<Reverse<Candidate<T>>>,
+}
+
+impl<T: ArithmeticInteger> Iterator for SortedDivisors<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let Reverse(current) = self.heap.pop()?;
+        for i in 0..current.limit {
+            let (multiplier, max_count) = &self.steps[i];
+            if current.counts[i] == *max_count {
+                continue;
+            }
+            let value = current.value.clone() * multiplier.clone();
+            if self.bound.as_ref().is_some_and(|b| &value > b) {
+                continue;
+            }
+            let mut counts = current.counts.clone();
+            counts[i] += 1;
+            self.heap.push(Reverse(Candidate {
+                value,
+                counts,
+                limit: i + 1,
+            }));
+        }
+        Some(current.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::arithmetic::ArithmeticFunctions;
+    use crate::{Factoring, Integer};
+
+    #[test]
+    fn small_numbers() {
+        for n in 1..300u64 {
+            let expected: Vec<u64> = (1..=n).filter(|d| n % d == 0).collect();
+            let f = n.factor_exponents();
+            let mut unsorted: Vec<u64> = f.divisors().collect();
+            unsorted.sort_unstable();
+            assert_eq!(unsorted, expected, "divisors({n})");
+            assert_eq!(f.divisors().sorted().collect::<Vec<_>>(), expected);
+            let bound = n / 3;
+            let bounded: Vec<u64> = expected.iter().copied().filter(|&d| d <= bound).collect();
+            let mut unsorted: Vec<u64> = f.divisors().bounded(bound).collect();
+            unsorted.sort_unstable();
+            assert_eq!(unsorted, bounded, "bounded divisors({n})");
+            assert_eq!(
+                f.divisors().bounded(bound).sorted().collect::<Vec<_>>(),
+                bounded
+            );
+            let unitary: Vec<u64> = expected
+                .iter()
+                .copied()
+                .filter(|&d| super::super::gcd(d, n / d) == 1)
+                .collect();
+            assert_eq!(f.unitary_divisors().sorted().collect::<Vec<_>>(), unitary);
+        }
+    }
+
+    #[test]
+    fn huge() {
+        // 2^200 * 3^200 * 5^200 has 201^3 divisors, only the first few are computed
+        let n = Integer::from(Integer::u_pow_u(30, 200));
+        let first: Vec<Integer> = n.clone().divisors().sorted().take(8).collect();
+        assert_eq!(first, [1, 2, 3, 4, 5, 6, 8, 9]);
+        assert_eq!(n.clone().divisors().take(1000).count(), 1000);
+        assert_eq!(n.divisors().bounded(Integer::from(100)).count(), 34);
+    }
+}
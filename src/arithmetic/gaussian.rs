@@ -0,0 +1,538 @@
+#![allow(clippy::many_single_char_names)]
+
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+use std::ops::{Add, Mul, Neg, Sub};
+
+use rug::Integer;
+
+use super::{checked_factor_exponents, from_u32, ModularSquareRoot};
+use crate::{
+    Factoring, FactoringBudget, FactoringEventSubscriptor, Factorization, PartialFactorization,
+    Primality,
+};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// Gaussian integer `re + im * i`
+///
+/// Factoring splits the norm into rational primes, and those into gaussian primes.
+/// The factors are normalized to the first quadrant, so their product is an associate of the factored number.
+///
+/// # Example
+/// ```
+/// use facto::arithmetic::GaussianInteger;
+/// use facto::Factoring;
+/// let z = GaussianInteger::new(5, 15);
+/// assert_eq!(z.norm(), 250);
+/// let factors: Vec<String> = z.factor().iter().map(ToString::to_string).collect();
+/// assert_eq!(factors, ["1+i", "1+2i", "2+i", "2+i"]);
+/// ```
+pub struct GaussianInteger {
+    /// Real part
+    pub re: Integer,
+    /// Imaginary part
+    pub im: Integer,
+}
+
+impl GaussianInteger {
+    #[must_use]
+    /// Create the gaussian integer `re + im * i`
+    pub fn new(re: impl Into<Integer>, im: impl Into<Integer>) -> Self {
+        Self {
+            re: re.into(),
+            im: im.into(),
+        }
+    }
+
+    #[must_use]
+    /// Norm `re² + im²`, multiplicative and equal to the squared absolute value
+    pub fn norm(&self) -> Integer {
+        Integer::from(self.re.square_ref()) + Integer::from(self.im.square_ref())
+    }
+
+    #[must_use]
+    /// Complex conjugate `re - im * i`
+    pub fn conj(&self) -> Self {
+        Self::new(self.re.clone(), Integer::from(-&self.im))
+    }
+
+    #[must_use]
+    /// Check if this is one of the units 1, i, -1 and -i
+    pub fn is_unit(&self) -> bool {
+        self.norm() == 1
+    }
+
+    #[must_use]
+    /// The associate in the first quadrant, with a positive real and a non-negative imaginary part
+    ///
+    /// Zero stays zero
+    pub fn normalized(self) -> Self {
+        let mut z = self;
+        if z.re == 0 && z.im == 0 {
+            return z;
+        }
+        while z.re <= 0 || z.im < 0 {
+            // Multiply by -i, rotating a quarter turn clockwise
+            z = Self::new(z.im, -z.re);
+        }
+        z
+    }
+
+    #[must_use]
+    /// Quotient of the division by `other`, if it is exact
+    ///
+    /// # Panics
+    /// If `other` is zero
+    pub fn checked_div(&self, other: &Self) -> Option<Self> {
+        let norm = other.norm();
+        assert!(norm != 0, "Division by zero");
+        let numerator = self * &other.conj();
+        if numerator.re.is_divisible(&norm) && numerator.im.is_divisible(&norm) {
+            Some(Self::new(numerator.re / &norm, numerator.im / &norm))
+        } else {
+            None
+        }
+    }
+
+    #[must_use]
+    /// Quotient rounded to the nearest gaussian integer, and the remainder, whose norm is less than the norm of `other`
+    ///
+    /// # Panics
+    /// If `other` is zero
+    pub fn div_rem(&self, other: &Self) -> (Self, Self) {
+        let norm = other.norm();
+        assert!(norm != 0, "Division by zero");
+        let numerator = self * &other.conj();
+        let (re, _) = numerator.re.div_rem_round(norm.clone());
+        let (im, _) = numerator.im.div_rem_round(norm);
+        let quotient = Self::new(re, im);
+        let remainder = self.clone() - &quotient * other;
+        (quotient, remainder)
+    }
+
+    #[must_use]
+    /// Greatest common divisor, normalized to the first quadrant
+    pub fn gcd(&self, other: &Self) -> Self {
+        let mut a = self.clone();
+        let mut b = other.clone();
+        while b != Self::default() {
+            let (_, r) = a.div_rem(&b);
+            a = b;
+            b = r;
+        }
+        a.normalized()
+    }
+}
+
+impl Add for GaussianInteger {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.re + rhs.re, self.im + rhs.im)
+    }
+}
+
+impl Sub for GaussianInteger {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.re - rhs.re, self.im - rhs.im)
+    }
+}
+
+impl Neg for GaussianInteger {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.re, -self.im)
+    }
+}
+
+impl Mul for &GaussianInteger {
+    type Output = GaussianInteger;
+
+    fn mul(self, rhs: Self) -> GaussianInteger {
+        GaussianInteger::new(
+            Integer::from(&self.re * &rhs.re) - Integer::from(&self.im * &rhs.im),
+            Integer::from(&self.re * &rhs.im) + Integer::from(&self.im * &rhs.re),
+        )
+    }
+}
+
+impl Mul for GaussianInteger {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        &self * &rhs
+    }
+}
+
+impl From<u8> for GaussianInteger {
+    fn from(x: u8) -> Self {
+        Self::new(x, 0)
+    }
+}
+
+impl Display for GaussianInteger {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let im = match self.im.to_i8() {
+            Some(1) => String::new(),
+            Some(-1) => "-".to_string(),
+            _ => self.im.to_string(),
+        };
+        if self.im == 0 {
+            write!(f, "{}", self.re)
+        } else if self.re == 0 {
+            write!(f, "{im}i")
+        } else if self.im < 0 {
+            write!(f, "{}{im}i", self.re)
+        } else {
+            write!(f, "{}+{im}i", self.re)
+        }
+    }
+}
+
+/// Solution of x² + y² = p for a prime p ≡ 1 (mod 4), by cornacchia's algorithm
+fn cornacchia(p: &Integer) -> (Integer, Integer) {
+    let root = (p.clone() - 1u32)
+        .sqrt_mod_prime(p.clone())
+        .expect("-1 is a square modulo primes congruent 1 mod 4");
+    let mut a = p.clone();
+    let mut b = root;
+    while Integer::from(b.square_ref()) > *p {
+        let r = a % &b;
+        a = b;
+        b = r;
+    }
+    let y = (p.clone() - Integer::from(b.square_ref())).sqrt();
+    (b, y)
+}
+
+/// Gaussian prime of norm `p`, for a rational prime `p` which is not congruent 3 mod 4
+fn split_prime(p: &Integer) -> GaussianInteger {
+    if *p == 2 {
+        return GaussianInteger::new(1, 1);
+    }
+    let (x, y) = cornacchia(p);
+    GaussianInteger::new(x, y)
+}
+
+/// Divide all gaussian primes above the rational prime `p` out of `z`
+fn split_out(z: &mut GaussianInteger, p: &Integer, primes: &mut Vec<GaussianInteger>) {
+    if p.mod_u(4) == 3 {
+        let q = GaussianInteger::new(p.clone(), 0);
+        while let Some(quotient) = z.checked_div(&q) {
+            *z = quotient;
+            primes.push(q.clone());
+        }
+        return;
+    }
+    let pi = split_prime(p);
+    for prime in [pi.conj().normalized(), pi] {
+        while let Some(quotient) = z.checked_div(&prime) {
+            *z = quotient;
+            primes.push(prime.clone());
+        }
+    }
+}
+
+impl Factoring for GaussianInteger {
+    /// # Panics
+    /// If `self` is zero
+    fn factor_events<T: FactoringEventSubscriptor<Self> + ?Sized>(
+        self,
+        events: &mut T,
+    ) -> Vec<Self> {
+        assert!(self != Self::default(), "Can not factor zero");
+        let mut z = self.clone();
+        let mut primes = vec![];
+        for (p, _) in self.norm().factor_exponents().factors() {
+            split_out(&mut z, p, &mut primes);
+        }
+        primes.sort_unstable();
+        if primes.len() == 1 {
+            events.is_prime(&self);
+        } else if primes.len() > 1 {
+            events.factorized(&self, &primes, &[], &[]);
+        }
+        primes
+    }
+
+    fn factor_with_budget(self, budget: &FactoringBudget) -> PartialFactorization<Self> {
+        if self == Self::default() {
+            return PartialFactorization {
+                primes: vec![],
+                composites: vec![],
+                unknown: vec![self],
+            };
+        }
+        let norm = self.norm().factor_with_budget(budget);
+        let mut z = self;
+        let mut primes = vec![];
+        for p in Factorization::from_primes(norm.primes).primes() {
+            split_out(&mut z, p, &mut primes);
+        }
+        primes.sort_unstable();
+        PartialFactorization {
+            primes,
+            composites: vec![],
+            unknown: if z.is_unit() { vec![] } else { vec![z] },
+        }
+    }
+}
+
+impl<T> Factorization<T>
+where
+    T: ModularSquareRoot + Into<Integer> + TryFrom<Integer>,
+{
+    #[must_use]
+    /// Number of ways to write the number as a² + b², counting signs and order
+    pub fn r2(&self) -> T {
+        let mut result = T::from(4);
+        for (p, e) in self.factors() {
+            match Into::<Integer>::into(p.clone()).mod_u(4) {
+                1 => result = result * from_u32(e + 1),
+                3 if e % 2 == 1 => return T::from(0),
+                _ => {}
+            }
+        }
+        result
+    }
+
+    #[must_use]
+    /// All pairs (a, b) with a ≤ b and a² + b² equal to the number, in ascending order of a
+    ///
+    /// The primes congruent 1 mod 4 are split into gaussian primes using cornacchia's algorithm,
+    /// every combination of the gaussian primes and their conjugates gives one representation.
+    ///
+    /// # Example
+    /// ```
+    /// use facto::Factoring;
+    /// assert_eq!(325u64.factor_exponents().sum_of_two_squares(), [(1, 18), (6, 17), (10, 15)]);
+    /// assert!(21u64.factor_exponents().sum_of_two_squares().is_empty());
+    /// ```
+    pub fn sum_of_two_squares(&self) -> Vec<(T, T)> {
+        let mut products = vec![GaussianInteger::new(1, 0)];
+        for (p, e) in self.factors() {
+            let p: Integer = p.clone().into();
+            let choices: Vec<GaussianInteger> = match p.mod_u(4) {
+                3 if e % 2 == 1 => return vec![],
+                3 => vec![pow(&GaussianInteger::new(p, 0), e / 2)],
+                2 => vec![pow(&GaussianInteger::new(1, 1), *e)],
+                _ => {
+                    let pi = split_prime(&p);
+                    let conj = pi.conj();
+                    (0..=*e)
+                        .map(|k| &pow(&pi, k) * &pow(&conj, e - k))
+                        .collect()
+                }
+            };
+            products = products
+                .iter()
+                .flat_map(|z| choices.iter().map(move |c| z * c))
+                .collect();
+        }
+        let mut result: Vec<(Integer, Integer)> = products
+            .into_iter()
+            .map(|z| {
+                let (a, b) = (z.re.abs(), z.im.abs());
+                if a <= b {
+                    (a, b)
+                } else {
+                    (b, a)
+                }
+            })
+            .collect();
+        result.sort_unstable();
+        result.dedup();
+        result
+            .into_iter()
+            .filter_map(|(a, b)| Some((T::try_from(a).ok()?, T::try_from(b).ok()?)))
+            .collect()
+    }
+}
+
+fn pow(z: &GaussianInteger, exponent: u32) -> GaussianInteger {
+    (0..exponent).fold(GaussianInteger::new(1, 0), |acc, _| &acc * z)
+}
+
+/// Representations of a number as sums of squares
+///
+/// # Example
+/// ```
+/// use facto::arithmetic::SumOfSquares;
+/// assert_eq!(50u64.sum_of_two_squares(), [(1, 7), (5, 5)]);
+/// assert_eq!(50u64.r2(), 12);
+/// let (a, b, c, d) = 7u64.sum_of_four_squares();
+/// assert_eq!(a * a + b * b + c * c + d * d, 7);
+/// ```
+pub trait SumOfSquares: ModularSquareRoot + Into<Integer> + TryFrom<Integer> {
+    #[must_use]
+    /// All pairs (a, b) with a ≤ b and a² + b² = `self`, see [`Factorization::sum_of_two_squares`]
+    ///
+    /// # Panics
+    /// If `self` is zero
+    fn sum_of_two_squares(self) -> Vec<(Self, Self)> {
+        checked_factor_exponents(self).sum_of_two_squares()
+    }
+
+    #[must_use]
+    /// Number of ways to write `self` as a² + b², see [`Factorization::r2`]
+    ///
+    /// # Panics
+    /// If `self` is zero
+    fn r2(self) -> Self {
+        checked_factor_exponents(self).r2()
+    }
+
+    #[must_use]
+    /// Some (a, b, c, d) with a² + b² + c² + d² = `self`
+    ///
+    /// Searches for a and b leaving a prime congruent 1 mod 4, or twice such a prime, which is split by cornacchia's algorithm.
+    fn sum_of_four_squares(self) -> (Self, Self, Self, Self) {
+        let (a, b, c, d) = four_squares(self.into());
+        let convert = |x: Integer| Self::try_from(x).unwrap_or_else(|_| unreachable!());
+        (convert(a), convert(b), convert(c), convert(d))
+    }
+}
+
+impl<T: ModularSquareRoot + Into<Integer> + TryFrom<Integer>> SumOfSquares for T {}
+
+/// Some (x, y) with x² + y² = `n`, if `n` is 0, a square, a prime congruent 1 mod 4 or twice such a prime
+fn quick_two_squares(n: &Integer) -> Option<(Integer, Integer)> {
+    if n.is_perfect_square() {
+        return Some((Integer::new(), n.clone().sqrt()));
+    }
+    if *n == 2 {
+        return Some((Integer::from(1), Integer::from(1)));
+    }
+    let (odd, twice) = if n.is_even() {
+        (Integer::from(n / 2u32), true)
+    } else {
+        (n.clone(), false)
+    };
+    if odd.mod_u(4) != 1 || !odd.clone().is_prime() {
+        return None;
+    }
+    let (x, y) = cornacchia(&odd);
+    if twice {
+        // 2(x² + y²) = (x + y)² + (x - y)²
+        Some((Integer::from(&x - &y).abs(), x + y))
+    } else {
+        Some((x, y))
+    }
+}
+
+fn four_squares(n: Integer) -> (Integer, Integer, Integer, Integer) {
+    // Representations of n / 4 give representations of n by doubling
+    let mut m = n;
+    let mut scale = Integer::from(1);
+    while m != 0 && m.is_divisible_u(4) {
+        m /= 4u32;
+        scale *= 2u32;
+    }
+    let mut a = Integer::from(m.sqrt_ref());
+    loop {
+        let rest = &m - Integer::from(a.square_ref());
+        let mut b = Integer::from(rest.sqrt_ref());
+        loop {
+            let last = &rest - Integer::from(b.square_ref());
+            if let Some((c, d)) = quick_two_squares(&last) {
+                return (a * &scale, b * &scale, c * &scale, d * scale);
+            }
+            if b == 0 {
+                break;
+            }
+            b -= 1u32;
+        }
+        a -= 1u32;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use super::{GaussianInteger, SumOfSquares};
+    use crate::{Factoring, Integer};
+
+    #[test]
+    fn gaussian_factoring() {
+        for re in -20..20i32 {
+            for im in -20..20i32 {
+                let z = GaussianInteger::new(re, im);
+                if z == GaussianInteger::default() {
+                    continue;
+                }
+                let factors = z.clone().factor();
+                for f in &factors {
+                    assert_eq!(f.clone().normalized(), *f);
+                    assert!(!f.is_unit());
+                }
+                let product = factors
+                    .iter()
+                    .fold(GaussianInteger::new(1, 0), |acc, f| &acc * f);
+                assert!(z.checked_div(&product).unwrap().is_unit(), "{}", z);
+            }
+        }
+    }
+
+    #[test]
+    fn arithmetic() {
+        let a = GaussianInteger::new(7, -3);
+        let b = GaussianInteger::new(2, 5);
+        let (q, r) = a.div_rem(&b);
+        assert_eq!(&q * &b + r.clone(), a);
+        assert!(r.norm() < b.norm());
+        assert_eq!((&a * &b).checked_div(&b), Some(a.clone()));
+        assert_eq!(a.checked_div(&b), None);
+        assert_eq!((&a * &b).gcd(&(&b * &b)), b.clone().normalized());
+        assert_eq!(a.to_string(), "7-3i");
+        assert_eq!(GaussianInteger::new(0, -1).to_string(), "-i");
+        assert_eq!(
+            -GaussianInteger::new(1, 1) - GaussianInteger::new(0, 1),
+            GaussianInteger::new(-1, -2)
+        );
+    }
+
+    #[test]
+    fn two_squares() {
+        for n in 1..500u64 {
+            let expected: Vec<(u64, u64)> = (0..=n)
+                .take_while(|a| 2 * a * a <= n)
+                .filter_map(|a| {
+                    let b = crate::util::NumUtil::integer_square_root(n - a * a);
+                    (a * a + b * b == n).then_some((a, b))
+                })
+                .collect();
+            assert_eq!(n.sum_of_two_squares(), expected, "{n}");
+            let signed = (-30..=30i64)
+                .flat_map(|a| (-30..=30i64).map(move |b| (a, b)))
+                .filter(|(a, b)| u64::try_from(a * a + b * b) == Ok(n))
+                .count() as u64;
+            if n <= 30 * 30 {
+                assert_eq!(n.r2(), signed, "r2({n})");
+            }
+        }
+        // 2^61 - 1 is congruent 3 mod 4
+        let p = (1u128 << 61) - 1;
+        assert!((p * 5 * 5 * 13).sum_of_two_squares().is_empty());
+        let n = 1_000_000_009u128 * 5 * 5 * 13;
+        let representations = n.sum_of_two_squares();
+        assert_eq!(representations.len(), 6);
+        for (a, b) in representations {
+            assert_eq!(a * a + b * b, n);
+        }
+    }
+
+    #[test]
+    fn four_squares() {
+        for n in 0..2000u64 {
+            let (a, b, c, d) = n.sum_of_four_squares();
+            assert_eq!(a * a + b * b + c * c + d * d, n);
+        }
+        let n = (Integer::from(1) << 300u32) * 3u32 + 7u32;
+        let (a, b, c, d) = n.clone().sum_of_four_squares();
+        assert_eq!(a.square() + b.square() + c.square() + d.square(), n);
+    }
+}
@@ -0,0 +1,346 @@
+mod discrete_log;
+mod divisors;
+mod gaussian;
+mod order;
+mod sqrt;
+
+use std::fmt::Debug;
+use std::ops::{Add, Div, Mul, Rem, Sub};
+
+pub use discrete_log::DiscreteLogarithm;
+pub use divisors::{Divisors, SortedDivisors};
+pub use gaussian::{GaussianInteger, SumOfSquares};
+pub use order::{MontgomeryField, MultiplicativeOrder};
+pub use sqrt::ModularSquareRoot;
+
+use crate::util::power;
+use crate::{Factoring, Factorization};
+
+/// Integer operations needed by the arithmetic functions
+///
+/// Implemented for all types providing them, in particular `u64`, `u128` and [`rug::Integer`]
+pub trait ArithmeticInteger:
+    Clone
+    + Debug
+    + Ord
+    + From<u8>
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Rem<Output = Self>
+{
+}
+
+impl<T> ArithmeticInteger for T where
+    T: Clone
+        + Debug
+        + Ord
+        + From<u8>
+        + Add<Output = Self>
+        + Sub<Output = Self>
+        + Mul<Output = Self>
+        + Div<Output = Self>
+        + Rem<Output = Self>
+{
+}
+
+/// Convert a `u32` into any [`ArithmeticInteger`], bit by bit
+fn from_u32<T: ArithmeticInteger>(x: u32) -> T {
+    (0..u32::BITS - x.leading_zeros())
+        .rev()
+        .fold(T::from(0), |acc, bit| {
+            acc.clone() + acc + T::from(u8::from(x >> bit & 1 == 1))
+        })
+}
+
+fn gcd<T: ArithmeticInteger>(mut a: T, mut b: T) -> T {
+    let zero = T::from(0);
+    while b != zero {
+        let r = a % b.clone();
+        a = b;
+        b = r;
+    }
+    a
+}
+
+fn lcm<T: ArithmeticInteger>(a: T, b: T) -> T {
+    let divisor = gcd(a.clone(), b.clone());
+    a / divisor * b
+}
+
+/// Arithmetic functions evaluated on a precomputed factorization
+///
+/// Results exceeding the integer type overflow, like the arithmetic operations of the type.
+///
+/// # Example
+/// ```
+/// use facto::{Factoring, Factorization};
+/// let f = 360u64.factor_exponents();
+/// assert_eq!(f.euler_phi(), 96);
+/// assert_eq!(f.sigma(1), 1170);
+/// assert_eq!(f.tau(), 24);
+/// assert_eq!(f.moebius(), 0);
+/// ```
+impl<T: ArithmeticInteger> Factorization<T> {
+    #[must_use]
+    /// Euler's totient φ, the count of numbers up to n coprime to n
+    pub fn euler_phi(&self) -> T {
+        self.factors().iter().fold(T::from(1), |acc, (p, e)| {
+            acc * power(p.clone(), e - 1) * (p.clone() - T::from(1))
+        })
+    }
+
+    #[must_use]
+    /// Carmichael's function λ, the exponent of the multiplicative group modulo n
+    pub fn carmichael_lambda(&self) -> T {
+        self.factors().iter().fold(T::from(1), |acc, (p, e)| {
+            let part = if *p == T::from(2) && *e >= 3 {
+                power(T::from(2), e - 2)
+            } else {
+                power(p.clone(), e - 1) * (p.clone() - T::from(1))
+            };
+            lcm(acc, part)
+        })
+    }
+
+    #[must_use]
+    /// Divisor function `σ_k`, the sum of the k-th powers of all divisors
+    pub fn sigma(&self, k: u32) -> T {
+        if k == 0 {
+            return self.tau();
+        }
+        self.factors().iter().fold(T::from(1), |acc, (p, e)| {
+            let p_k = power(p.clone(), k);
+            // Geometric series 1 + p^k + ... + p^(ke), summed directly to avoid overflowing in p^(k(e+1))
+            let mut sum = T::from(1);
+            let mut term = T::from(1);
+            for _ in 0..*e {
+                term = term * p_k.clone();
+                sum = sum + term.clone();
+            }
+            acc * sum
+        })
+    }
+
+    #[must_use]
+    /// Number of divisors τ, also known as `σ_0`
+    pub fn tau(&self) -> T {
+        self.factors()
+            .iter()
+            .fold(T::from(1), |acc, (_, e)| acc * from_u32(e + 1))
+    }
+
+    #[must_use]
+    /// Möbius function μ, zero if n is not squarefree, otherwise -1 to the power of the number of prime factors
+    pub fn moebius(&self) -> i8 {
+        if !self.is_squarefree() {
+            return 0;
+        }
+        if self.factors().len() % 2 == 0 {
+            1
+        } else {
+            -1
+        }
+    }
+
+    #[must_use]
+    /// Number of distinct prime factors ω
+    pub fn omega(&self) -> u64 {
+        self.factors().len() as u64
+    }
+
+    #[must_use]
+    /// Number of prime factors Ω, counted with multiplicity
+    pub fn big_omega(&self) -> u64 {
+        self.factors().iter().map(|(_, e)| u64::from(*e)).sum()
+    }
+
+    #[must_use]
+    /// Radical, also called kernel, the product of the distinct prime factors
+    pub fn radical(&self) -> T {
+        self.primes().fold(T::from(1), |acc, p| acc * p.clone())
+    }
+
+    #[must_use]
+    /// Check if no prime divides n more than once
+    pub fn is_squarefree(&self) -> bool {
+        self.factors().iter().all(|(_, e)| *e == 1)
+    }
+
+    #[must_use]
+    /// Squarefree core, the smallest number c such that n / c is a square
+    pub fn core(&self) -> T {
+        self.factors()
+            .iter()
+            .filter(|(_, e)| e % 2 == 1)
+            .fold(T::from(1), |acc, (p, _)| acc * p.clone())
+    }
+}
+
+/// Arithmetic functions of a number, factoring it first
+///
+/// To evaluate several functions of the same number, factor it once using [`Factoring::factor_exponents`]
+/// and use the methods of [`Factorization`] instead.
+///
+/// # Panics
+/// All methods panic if the number is zero
+///
+/// # Example
+/// ```
+/// use facto::arithmetic::ArithmeticFunctions;
+/// use facto::Integer;
+/// assert_eq!(36u64.euler_phi(), 12);
+/// assert_eq!(36u128.carmichael_lambda(), 6);
+/// assert_eq!(Integer::from(36).sigma(2), 1 + 4 + 9 + 16 + 36 + 81 + 144 + 324 + 1296);
+/// ```
+pub trait ArithmeticFunctions: Factoring + ArithmeticInteger {
+    #[must_use]
+    /// Euler's totient φ, see [`Factorization::euler_phi`]
+    fn euler_phi(self) -> Self {
+        checked_factor_exponents(self).euler_phi()
+    }
+
+    #[must_use]
+    /// Carmichael's function λ, see [`Factorization::carmichael_lambda`]
+    fn carmichael_lambda(self) -> Self {
+        checked_factor_exponents(self).carmichael_lambda()
+    }
+
+    #[must_use]
+    /// Divisor function `σ_k`, see [`Factorization::sigma`]
+    fn sigma(self, k: u32) -> Self {
+        checked_factor_exponents(self).sigma(k)
+    }
+
+    #[must_use]
+    /// Number of divisors τ, see [`Factorization::tau`]
+    fn tau(self) -> Self {
+        checked_factor_exponents(self).tau()
+    }
+
+    #[must_use]
+    /// Möbius function μ, see [`Factorization::moebius`]
+    fn moebius(self) -> i8 {
+        checked_factor_exponents(self).moebius()
+    }
+
+    #[must_use]
+    /// Number of distinct prime factors ω, see [`Factorization::omega`]
+    fn omega(self) -> u64 {
+        checked_factor_exponents(self).omega()
+    }
+
+    #[must_use]
+    /// Number of prime factors Ω, see [`Factorization::big_omega`]
+    fn big_omega(self) -> u64 {
+        checked_factor_exponents(self).big_omega()
+    }
+
+    #[must_use]
+    /// Radical, see [`Factorization::radical`]
+    fn radical(self) -> Self {
+        checked_factor_exponents(self).radical()
+    }
+
+    #[must_use]
+    #[allow(clippy::wrong_self_convention)]
+    /// Squarefree test, see [`Factorization::is_squarefree`]
+    fn is_squarefree(self) -> bool {
+        checked_factor_exponents(self).is_squarefree()
+    }
+
+    #[must_use]
+    /// Squarefree core, see [`Factorization::core`]
+    fn core(self) -> Self {
+        checked_factor_exponents(self).core()
+    }
+
+    /// Divisors in no particular order, see [`Factorization::divisors`]
+    fn divisors(self) -> Divisors<Self> {
+        checked_factor_exponents(self).divisors()
+    }
+
+    /// Unitary divisors in no particular order, see [`Factorization::unitary_divisors`]
+    fn unitary_divisors(self) -> Divisors<Self> {
+        checked_factor_exponents(self).unitary_divisors()
+    }
+}
+
+impl<T: Factoring + ArithmeticInteger> ArithmeticFunctions for T {}
+
+fn checked_factor_exponents<T: Factoring + ArithmeticInteger>(n: T) -> Factorization<T> {
+    assert!(n != T::from(0), "Arithmetic functions of 0 are undefined");
+    n.factor_exponents()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArithmeticFunctions;
+    use crate::{Factoring, Integer};
+
+    /// Naive evaluation by iterating over all numbers up to n
+    fn naive(n: u64) -> (u64, u64, u64, u64) {
+        let divisors: Vec<u64> = (1..=n).filter(|d| n % d == 0).collect();
+        let phi = (1..=n).filter(|&k| super::gcd(k, n) == 1).count() as u64;
+        let lambda = (1..=n)
+            .find(|&m| {
+                (1..=n)
+                    .filter(|&k| super::gcd(k, n) == 1)
+                    .all(|k| (0..m).fold(1, |acc, _| acc * k % n) == 1 % n)
+            })
+            .unwrap();
+        let sigma2 = divisors.iter().map(|d| d * d).sum();
+        (phi, lambda, sigma2, divisors.len() as u64)
+    }
+
+    #[test]
+    fn small_numbers() {
+        for n in 1..200u64 {
+            let (phi, lambda, sigma2, tau) = naive(n);
+            assert_eq!(n.euler_phi(), phi, "phi({n})");
+            assert_eq!(n.carmichael_lambda(), lambda, "lambda({n})");
+            assert_eq!(n.sigma(2), sigma2, "sigma_2({n})");
+            assert_eq!(n.sigma(0), tau, "sigma_0({n})");
+            assert_eq!(n.tau(), tau, "tau({n})");
+            let squarefree = (2..n).all(|d| n % (d * d) != 0);
+            assert_eq!(n.is_squarefree(), squarefree, "squarefree({n})");
+            let core = n.core();
+            assert_eq!(
+                crate::util::NumUtil::integer_square_root(n / core).pow(2),
+                n / core,
+                "core({n})"
+            );
+        }
+    }
+
+    #[test]
+    fn counts() {
+        assert_eq!(1u64.moebius(), 1);
+        assert_eq!(30u64.moebius(), -1);
+        assert_eq!(6u64.moebius(), 1);
+        assert_eq!(12u64.moebius(), 0);
+        assert_eq!(360u64.omega(), 3);
+        assert_eq!(360u64.big_omega(), 6);
+        assert_eq!(360u64.radical(), 30);
+        assert_eq!(360u64.core(), 10);
+    }
+
+    #[test]
+    fn wide_types() {
+        const P: u128 = 4_294_967_279;
+        const Q: u128 = 4_294_967_291;
+        let n = P * P * Q * 8;
+        assert_eq!(n.euler_phi(), P * (P - 1) * (Q - 1) * 4);
+        assert_eq!(n.carmichael_lambda(), P * (P - 1) * (Q - 1) / 2);
+        assert_eq!(n.sigma(1), (1 + P + P * P) * (1 + Q) * 15);
+        assert_eq!(n.radical(), 2 * P * Q);
+
+        let m = Integer::from(n) << 100u32;
+        let f = m.clone().factor_exponents();
+        assert_eq!(f.euler_phi(), Integer::from(n.euler_phi()) << 100u32);
+        assert_eq!(f.tau(), 3 * 2 * 104);
+        assert_eq!(f.core(), 2 * Integer::from(Q));
+        assert_eq!(m.big_omega(), 106);
+    }
+}
@@ -0,0 +1,446 @@
+use std::convert::TryFrom;
+
+use redc::{Field, PrimIntField, Redc, RugField};
+use rug::Integer;
+use twoword::TwoWord;
+
+use super::{checked_factor_exponents, ArithmeticFunctions, ArithmeticInteger};
+use crate::util::power;
+use crate::{Factoring, Factorization};
+
+impl<T: ArithmeticInteger + Factoring> Factorization<T> {
+    #[must_use]
+    /// Factorization of Euler's totient φ, factoring p - 1 for every prime p
+    pub fn euler_phi_factorization(&self) -> Self {
+        self.factors().iter().fold(Self::one(), |acc, (p, e)| {
+            &(&acc * &Self::from_factors(vec![(p.clone(), e - 1)]))
+                * &(p.clone() - T::from(1)).factor_exponents()
+        })
+    }
+
+    #[must_use]
+    /// Factorization of Carmichael's function λ, factoring p - 1 for every odd prime p
+    pub fn carmichael_lambda_factorization(&self) -> Self {
+        self.factors().iter().fold(Self::one(), |acc, (p, e)| {
+            let part = if *p == T::from(2) {
+                let exponent = if *e >= 3 { e - 2 } else { e - 1 };
+                Self::from_factors(vec![(T::from(2), exponent)])
+            } else {
+                &Self::from_factors(vec![(p.clone(), e - 1)])
+                    * &(p.clone() - T::from(1)).factor_exponents()
+            };
+            acc.lcm(&part)
+        })
+    }
+}
+
+/// Multiplicative order and primitive roots of the units modulo a number
+///
+/// # Example
+/// ```
+/// use facto::arithmetic::MultiplicativeOrder;
+/// assert_eq!(2u64.multiplicative_order(7), Some(3));
+/// assert_eq!(6u64.multiplicative_order(9), None);
+/// assert!(3u64.is_primitive_root(7));
+/// assert_eq!(18u64.primitive_root(), Some(5));
+/// assert_eq!(8u64.primitive_root(), None);
+/// // Generator of the subgroup of order 11 of the units modulo 23
+/// assert_eq!(23u64.subgroup_generator(11), Some(4));
+/// ```
+pub trait MultiplicativeOrder: ArithmeticFunctions {
+    #[must_use]
+    /// `self` to the power of `exponent`, modulo `modulus`
+    ///
+    /// Odd moduli use montgomery multiplication
+    fn power_mod(&self, exponent: &Self, modulus: &Self) -> Self;
+
+    #[must_use]
+    /// `self` times `other`, modulo `modulus`
+    fn mul_mod(&self, other: &Self, modulus: &Self) -> Self;
+
+    /// Montgomery arithmetic modulo an odd number, for many multiplications with the same modulus
+    type Montgomery: MontgomeryField<Self>;
+
+    #[must_use]
+    /// Set up montgomery arithmetic modulo the odd `modulus`
+    fn montgomery_field(modulus: &Self) -> Self::Montgomery;
+
+    #[must_use]
+    /// Smallest k > 0 with `self`^k ≡ 1 (mod `n`)
+    ///
+    /// # Returns
+    /// `None` if `self` and `n` are not coprime
+    ///
+    /// # Panics
+    /// If `n` is zero
+    fn multiplicative_order(self, n: Self) -> Option<Self> {
+        let lambda = checked_factor_exponents(n.clone()).carmichael_lambda_factorization();
+        self.multiplicative_order_with(&n, &lambda)
+    }
+
+    #[must_use]
+    /// Multiplicative order of `self` modulo `n`, given the factorization of a multiple of the order
+    ///
+    /// The multiple can be λ(n), see [`Factorization::carmichael_lambda_factorization`], or n - 1 if n is prime.
+    ///
+    /// # Returns
+    /// `None` if `self` and `n` are not coprime, or if the order does not divide `multiple`
+    fn multiplicative_order_with(self, n: &Self, multiple: &Factorization<Self>) -> Option<Self> {
+        let one = Self::from(1) % n.clone();
+        let mut order = multiple.value();
+        if self.power_mod(&order, n) != one {
+            return None;
+        }
+        for (p, e) in multiple.factors() {
+            for _ in 0..*e {
+                let candidate = order.clone() / p.clone();
+                if self.power_mod(&candidate, n) != one {
+                    break;
+                }
+                order = candidate;
+            }
+        }
+        Some(order)
+    }
+
+    #[must_use]
+    #[allow(clippy::wrong_self_convention)]
+    /// Check if `self` generates the units modulo `n`
+    ///
+    /// # Panics
+    /// If `n` is zero
+    fn is_primitive_root(self, n: Self) -> bool {
+        let phi = checked_factor_exponents(n.clone()).euler_phi_factorization();
+        self.is_primitive_root_with(&n, &phi)
+    }
+
+    #[must_use]
+    /// Check if `self` generates the units modulo `n`, given the factorization of φ(n)
+    fn is_primitive_root_with(&self, n: &Self, phi: &Factorization<Self>) -> bool {
+        let one = Self::from(1) % n.clone();
+        let phi_value = phi.value();
+        self.power_mod(&phi_value, n) == one
+            && phi
+                .primes()
+                .all(|p| self.power_mod(&(phi_value.clone() / p.clone()), n) != one)
+    }
+
+    #[must_use]
+    /// Smallest primitive root modulo `self`
+    ///
+    /// # Returns
+    /// `None` if there is no primitive root, i.e. `self` is not 1, 2, 4, p^k or 2p^k for an odd prime p
+    ///
+    /// # Panics
+    /// If `self` is zero
+    fn primitive_root(self) -> Option<Self> {
+        let n = checked_factor_exponents(self);
+        let two = Self::from(2);
+        let odd: Vec<_> = n.factors().iter().filter(|(p, _)| *p != two).collect();
+        let cyclic = match n.exponent(&two) {
+            0 | 1 => odd.len() <= 1,
+            2 => odd.is_empty(),
+            _ => false,
+        };
+        if !cyclic {
+            return None;
+        }
+        let phi = n.euler_phi_factorization();
+        let modulus = n.value();
+        let mut g = Self::from(1) % modulus.clone();
+        while !g.is_primitive_root_with(&modulus, &phi) {
+            g = g + Self::from(1);
+        }
+        Some(g)
+    }
+
+    #[must_use]
+    /// Element of multiplicative order `order` modulo `self`, generating the cyclic subgroup of that order
+    ///
+    /// # Returns
+    /// `None` if there is no such element, i.e. `order` does not divide λ(`self`)
+    ///
+    /// # Panics
+    /// If `self` or `order` is zero
+    fn subgroup_generator(self, order: Self) -> Option<Self> {
+        let lambda = checked_factor_exponents(self.clone()).carmichael_lambda_factorization();
+        let order_factorization = checked_factor_exponents(order.clone());
+        if !lambda.is_divisible_by(&order_factorization) {
+            return None;
+        }
+        let cofactor = lambda.value() / order;
+        let mut a = Self::from(1) % self.clone();
+        loop {
+            let candidate = a.power_mod(&cofactor, &self);
+            if candidate
+                .clone()
+                .multiplicative_order_with(&self, &order_factorization)
+                .is_some_and(|x| x == order_factorization.value())
+            {
+                return Some(candidate);
+            }
+            a = a + Self::from(1);
+        }
+    }
+}
+
+/// Multiplication modulo an odd number in montgomery form, implemented for the fields of the `redc` crate
+///
+/// Numbers below the modulus are converted into montgomery form, multiplied, and converted back.
+/// Sums and differences modulo the odd number are the same in montgomery form.
+pub trait MontgomeryField<T> {
+    #[must_use]
+    /// Montgomery form of `x`
+    fn to_montgomery(&self, x: &T) -> T;
+
+    #[must_use]
+    /// Number represented by `x` in montgomery form
+    fn to_normal(&self, x: &T) -> T;
+
+    #[must_use]
+    /// Product of `x` and `y` in montgomery form
+    fn mul(&self, x: &T, y: &T) -> T;
+}
+
+impl MontgomeryField<u64> for PrimIntField<u64> {
+    fn to_montgomery(&self, x: &u64) -> u64 {
+        Redc::to_montgomery(*x, self)
+    }
+
+    fn to_normal(&self, x: &u64) -> u64 {
+        Redc::to_normal(*x, self)
+    }
+
+    fn mul(&self, x: &u64, y: &u64) -> u64 {
+        self.redc(u128::from(*x) * u128::from(*y))
+    }
+}
+
+impl MontgomeryField<u128> for PrimIntField<u128> {
+    fn to_montgomery(&self, x: &u128) -> u128 {
+        Redc::to_montgomery(*x, self)
+    }
+
+    fn to_normal(&self, x: &u128) -> u128 {
+        Redc::to_normal(*x, self)
+    }
+
+    fn mul(&self, x: &u128, y: &u128) -> u128 {
+        self.redc(TwoWord::mult(*x, *y))
+    }
+}
+
+impl MontgomeryField<Integer> for RugField {
+    fn to_montgomery(&self, x: &Integer) -> Integer {
+        Redc::to_montgomery(x.clone(), self)
+    }
+
+    fn to_normal(&self, x: &Integer) -> Integer {
+        Redc::to_normal(x.clone(), self)
+    }
+
+    fn mul(&self, x: &Integer, y: &Integer) -> Integer {
+        self.redc(Integer::from(x * y))
+    }
+}
+
+/// Combine `x` modulo `modulus` and `residue` modulo `p^e` into a number modulo `modulus * p^e`
+///
+/// `modulus` must not be divisible by `p`, it is inverted modulo `p^e` by Euler's theorem.
+pub fn crt<T: MultiplicativeOrder>(x: &T, modulus: &T, residue: &T, p: &T, e: u32) -> T {
+    let prime_power = power(p.clone(), e);
+    let phi = power(p.clone(), e - 1) * (p.clone() - T::from(1));
+    let inverse =
+        (modulus.clone() % prime_power.clone()).power_mod(&(phi - T::from(1)), &prime_power);
+    let difference = sub_mod(
+        &(residue.clone() % prime_power.clone()),
+        &(x.clone() % prime_power.clone()),
+        &prime_power,
+    );
+    x.clone() + modulus.clone() * difference.mul_mod(&inverse, &prime_power)
+}
+
+/// `x + y` modulo `n`, for `x` and `y` below `n`, without overflowing near the maximum of the type
+pub fn add_mod<T: ArithmeticInteger>(x: &T, y: &T, n: &T) -> T {
+    let complement = n.clone() - y.clone();
+    if *x >= complement {
+        x.clone() - complement
+    } else {
+        x.clone() + y.clone()
+    }
+}
+
+/// `x - y` modulo `n`, for `x` and `y` below `n`, without overflowing near the maximum of the type
+pub fn sub_mod<T: ArithmeticInteger>(x: &T, y: &T, n: &T) -> T {
+    if x >= y {
+        x.clone() - y.clone()
+    } else {
+        x.clone() + (n.clone() - y.clone())
+    }
+}
+
+impl MultiplicativeOrder for u64 {
+    type Montgomery = PrimIntField<Self>;
+
+    fn montgomery_field(modulus: &Self) -> Self::Montgomery {
+        modulus.setup_field()
+    }
+
+    fn mul_mod(&self, other: &Self, modulus: &Self) -> Self {
+        let product = u128::from(*self) * u128::from(*other) % u128::from(*modulus);
+        Self::try_from(product).unwrap_or_else(|_| unreachable!())
+    }
+
+    fn power_mod(&self, exponent: &Self, modulus: &Self) -> Self {
+        if *modulus == 1 {
+            return 0;
+        }
+        if modulus % 2 == 1 {
+            let field = modulus.setup_field();
+            return self
+                .to_montgomery(&field)
+                .mod_pow(*exponent, &field)
+                .to_normal(&field);
+        }
+        let modulus = u128::from(*modulus);
+        let mut base = u128::from(*self) % modulus;
+        let mut exponent = *exponent;
+        let mut result = 1;
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result * base % modulus;
+            }
+            base = base * base % modulus;
+            exponent >>= 1;
+        }
+        Self::try_from(result).unwrap_or_else(|_| unreachable!())
+    }
+}
+
+impl MultiplicativeOrder for u128 {
+    type Montgomery = PrimIntField<Self>;
+
+    fn montgomery_field(modulus: &Self) -> Self::Montgomery {
+        modulus.setup_field()
+    }
+
+    fn mul_mod(&self, other: &Self, modulus: &Self) -> Self {
+        if let (Ok(a), Ok(b)) = (u64::try_from(*self), u64::try_from(*other)) {
+            return Self::from(a) * Self::from(b) % modulus;
+        }
+        (Integer::from(*self) * other % modulus).to_u128_wrapping()
+    }
+
+    fn power_mod(&self, exponent: &Self, modulus: &Self) -> Self {
+        if *modulus == 1 {
+            return 0;
+        }
+        if let (Ok(modulus), Ok(base)) = (u64::try_from(*modulus), u64::try_from(*self)) {
+            if let Ok(exponent) = u64::try_from(*exponent) {
+                return Self::from(base.power_mod(&exponent, &modulus));
+            }
+        }
+        if modulus % 2 == 1 {
+            let field = modulus.setup_field();
+            return self
+                .to_montgomery(&field)
+                .mod_pow(*exponent, &field)
+                .to_normal(&field);
+        }
+        Integer::from(*self)
+            .pow_mod(&Integer::from(*exponent), &Integer::from(*modulus))
+            .map_or(0, |x| x.to_u128_wrapping())
+    }
+}
+
+impl MultiplicativeOrder for Integer {
+    type Montgomery = RugField;
+
+    fn montgomery_field(modulus: &Self) -> Self::Montgomery {
+        modulus.clone().setup_field()
+    }
+
+    fn mul_mod(&self, other: &Self, modulus: &Self) -> Self {
+        Self::from(self * other) % modulus
+    }
+
+    fn power_mod(&self, exponent: &Self, modulus: &Self) -> Self {
+        if *modulus == 1 {
+            return Self::new();
+        }
+        if modulus.is_odd() {
+            let field = modulus.clone().setup_field();
+            return self
+                .clone()
+                .to_montgomery(&field)
+                .mod_pow(exponent.clone(), &field)
+                .to_normal(&field);
+        }
+        Self::from(
+            self.pow_mod_ref(exponent, modulus)
+                .unwrap_or_else(|| unreachable!()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MultiplicativeOrder;
+    use crate::arithmetic::ArithmeticFunctions;
+    use crate::Integer;
+
+    fn naive_order(a: u64, n: u64) -> Option<u64> {
+        (1..=n).find(|&k| (0..k).fold(1 % n, |acc, _| acc * a % n) == 1 % n)
+    }
+
+    #[test]
+    fn small_numbers() {
+        for n in 1..120u64 {
+            let lambda = n.carmichael_lambda();
+            let phi = n.euler_phi();
+            for a in 0..n {
+                let order = a.multiplicative_order(n);
+                assert_eq!(order, naive_order(a, n), "order of {a} mod {n}");
+                assert_eq!(a.is_primitive_root(n), order == Some(phi), "{a} mod {n}");
+                assert_eq!(
+                    u128::from(a).multiplicative_order(u128::from(n)),
+                    order.map(u128::from)
+                );
+            }
+            let expected = (0..n).find(|&g| g.multiplicative_order(n) == Some(phi));
+            assert_eq!(n.primitive_root(), expected, "primitive root mod {n}");
+            for order in 1..=lambda {
+                let generator = n.subgroup_generator(order);
+                if lambda % order == 0 {
+                    assert_eq!(
+                        generator.and_then(|g| g.multiplicative_order(n)),
+                        Some(order)
+                    );
+                } else {
+                    assert_eq!(generator, None);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn wide_types() {
+        let p = (1u128 << 127) - 1;
+        let g = p.primitive_root().unwrap();
+        assert_eq!(g.multiplicative_order(p), Some(p - 1));
+        let n = Integer::from(p) * 2u32;
+        let h = n.clone().primitive_root().unwrap();
+        assert!(h.clone().is_primitive_root(n.clone()));
+        assert_eq!(h.multiplicative_order(n), Some(Integer::from(p - 1)));
+        let exponent = Integer::from(p).square();
+        let modulus = Integer::from(1) << 200u32;
+        let expected = Integer::from(Integer::from(3).pow_mod_ref(&exponent, &modulus).unwrap());
+        assert_eq!(Integer::from(3).power_mod(&exponent, &modulus), expected);
+        assert_eq!(
+            Integer::from(3).power_mod(&exponent, &(modulus + 1u32)),
+            Integer::from(3)
+                .pow_mod(&exponent, &((Integer::from(1) << 200u32) + 1u32))
+                .unwrap()
+        );
+    }
+}
@@ -0,0 +1,500 @@
+#![allow(clippy::many_single_char_names)]
+
+use super::order::{add_mod, crt, sub_mod};
+use super::{checked_factor_exponents, ArithmeticInteger, MontgomeryField, MultiplicativeOrder};
+use crate::util::power;
+use crate::Factorization;
+
+/// Square roots modulo a number, and the jacobi and kronecker symbols
+///
+/// # Example
+/// ```
+/// use facto::arithmetic::ModularSquareRoot;
+/// assert_eq!(5u64.jacobi(21), 1);
+/// assert_eq!(10u64.sqrt_mod_prime(13), Some(6));
+/// assert_eq!(4u64.sqrt_mod_all(15), vec![2, 7, 8, 13]);
+/// assert_eq!(3u64.sqrt_mod(15), None);
+/// ```
+pub trait ModularSquareRoot: MultiplicativeOrder {
+    #[must_use]
+    /// Jacobi symbol (`self` / `n`)
+    ///
+    /// # Panics
+    /// If `n` is even
+    fn jacobi(self, n: Self) -> i8 {
+        assert!(
+            n.clone() % Self::from(2) == Self::from(1),
+            "Jacobi symbol needs an odd modulus"
+        );
+        let mut a = rem_euclid(self, &n);
+        let mut n = n;
+        let mut result = 1;
+        while a != Self::from(0) {
+            while a.clone() % Self::from(2) == Self::from(0) {
+                a = a / Self::from(2);
+                let r = n.clone() % Self::from(8);
+                if r == Self::from(3) || r == Self::from(5) {
+                    result = -result;
+                }
+            }
+            std::mem::swap(&mut a, &mut n);
+            if a.clone() % Self::from(4) == Self::from(3)
+                && n.clone() % Self::from(4) == Self::from(3)
+            {
+                result = -result;
+            }
+            a = a % n.clone();
+        }
+        if n == Self::from(1) {
+            result
+        } else {
+            0
+        }
+    }
+
+    #[must_use]
+    /// Kronecker symbol (`self` / `n`), extending the jacobi symbol to even `n`
+    fn kronecker(self, n: Self) -> i8 {
+        let two = Self::from(2);
+        if n == Self::from(0) {
+            return i8::from(self == Self::from(1));
+        }
+        let mut n = n;
+        let mut result = 1;
+        while n.clone() % two.clone() == Self::from(0) {
+            n = n / two.clone();
+            let r = rem_euclid(self.clone(), &Self::from(8));
+            if r.clone() % two.clone() == Self::from(0) {
+                return 0;
+            }
+            if r == Self::from(3) || r == Self::from(5) {
+                result = -result;
+            }
+        }
+        result * self.jacobi(n)
+    }
+
+    #[must_use]
+    /// Smallest square root of `self` modulo the prime `p`
+    ///
+    /// Uses Tonelli–Shanks, or Cipolla's algorithm if p - 1 is divisible by a large power of two.
+    /// The primality of `p` is not checked.
+    ///
+    /// # Returns
+    /// `None` if `self` is a quadratic nonresidue modulo `p`
+    fn sqrt_mod_prime(self, p: Self) -> Option<Self> {
+        let a = self % p.clone();
+        if a == Self::from(0) || p == Self::from(2) {
+            return Some(a);
+        }
+        let p_minus_one = p.clone() - Self::from(1);
+        if a.power_mod(&(p_minus_one.clone() / Self::from(2)), &p) != Self::from(1) {
+            return None;
+        }
+        let (s, _) = split_powers_of_two(p_minus_one);
+        let root = if s == 1 {
+            a.power_mod(&((p.clone() + Self::from(1)) / Self::from(4)), &p)
+        } else if s * s <= bit_length(p.clone()) {
+            tonelli_shanks(&a, &p)
+        } else {
+            cipolla(&a, &p)
+        };
+        Some(root.clone().min(p - root))
+    }
+
+    #[must_use]
+    /// Some square root of `self` modulo `n`, factoring `n`
+    ///
+    /// # Returns
+    /// `None` if `self` is not a square modulo `n`
+    ///
+    /// # Panics
+    /// If `n` is zero
+    fn sqrt_mod(self, n: Self) -> Option<Self> {
+        self.sqrt_mod_with(&checked_factor_exponents(n))
+    }
+
+    #[must_use]
+    /// Some square root of `self` modulo the number factored by `n`
+    ///
+    /// The roots modulo every prime power are lifted from the prime using Hensel's lemma, and combined by the chinese remainder theorem.
+    fn sqrt_mod_with(self, n: &Factorization<Self>) -> Option<Self> {
+        let mut result = Self::from(0);
+        let mut modulus = Self::from(1);
+        for (p, e) in n.factors() {
+            let roots = prime_power_roots(&self, p, *e)?;
+            result = crt(&result, &modulus, &roots.bases[0], p, *e);
+            modulus = modulus * power(p.clone(), *e);
+        }
+        Some(result)
+    }
+
+    #[must_use]
+    /// All square roots of `self` modulo `n` in ascending order, factoring `n`
+    ///
+    /// If `self` and `n` share square factors, the number of roots can be very large.
+    ///
+    /// # Panics
+    /// If `n` is zero
+    fn sqrt_mod_all(self, n: Self) -> Vec<Self> {
+        self.sqrt_mod_all_with(&checked_factor_exponents(n))
+    }
+
+    #[must_use]
+    /// All square roots of `self` modulo the number factored by `n`, in ascending order
+    fn sqrt_mod_all_with(self, n: &Factorization<Self>) -> Vec<Self> {
+        let mut result = vec![Self::from(0)];
+        let mut modulus = Self::from(1);
+        for (p, e) in n.factors() {
+            let Some(roots) = prime_power_roots(&self, p, *e) else {
+                return vec![];
+            };
+            let prime_power = power(p.clone(), *e);
+            let mut residues = vec![];
+            for base in &roots.bases {
+                let mut x = base.clone();
+                residues.push(x.clone());
+                while prime_power.clone() - x.clone() > roots.step {
+                    x = x + roots.step.clone();
+                    residues.push(x.clone());
+                }
+            }
+            result = result
+                .iter()
+                .flat_map(|x| {
+                    residues
+                        .iter()
+                        .map(|r| crt(x, &modulus, r, p, *e))
+                        .collect::<Vec<_>>()
+                })
+                .collect();
+            modulus = modulus * prime_power;
+        }
+        result.sort_unstable();
+        result
+    }
+}
+
+impl<T: MultiplicativeOrder> ModularSquareRoot for T {}
+
+/// Non-negative remainder of `a` modulo the positive `n`, as the remainder truncates for signed integers
+fn rem_euclid<T: ArithmeticInteger>(a: T, n: &T) -> T {
+    let r = a % n.clone();
+    if r < T::from(0) {
+        r + n.clone()
+    } else {
+        r
+    }
+}
+
+/// Square roots modulo `p^e`, being every `bases[i] + k * step` below `p^e`
+struct PrimePowerRoots<T> {
+    bases: Vec<T>,
+    step: T,
+}
+
+/// Split `n` into `2^s * q` with odd `q`
+fn split_powers_of_two<T: ArithmeticInteger>(mut n: T) -> (u32, T) {
+    let mut s = 0;
+    while n.clone() % T::from(2) == T::from(0) {
+        n = n / T::from(2);
+        s += 1;
+    }
+    (s, n)
+}
+
+fn bit_length<T: ArithmeticInteger>(mut n: T) -> u32 {
+    let mut result = 0;
+    while n > T::from(0) {
+        n = n / T::from(2);
+        result += 1;
+    }
+    result
+}
+
+/// Some quadratic nonresidue modulo the odd prime `p`
+fn nonresidue<T: ModularSquareRoot>(p: &T) -> T {
+    let mut z = T::from(2);
+    while z.clone().jacobi(p.clone()) != -1 {
+        z = z + T::from(1);
+    }
+    z
+}
+
+/// Square root of the quadratic residue `a` modulo the odd prime `p`
+fn tonelli_shanks<T: ModularSquareRoot>(a: &T, p: &T) -> T {
+    let field = T::montgomery_field(p);
+    let (s, q) = split_powers_of_two(p.clone() - T::from(1));
+    let one = field.to_montgomery(&T::from(1));
+    let mut m = s;
+    let mut c = field.to_montgomery(&nonresidue(p).power_mod(&q, p));
+    let mut t = field.to_montgomery(&a.power_mod(&q, p));
+    let mut r = field.to_montgomery(&a.power_mod(&((q + T::from(1)) / T::from(2)), p));
+    while t != one {
+        // Least i with t^(2^i) = 1
+        let mut i = 0;
+        let mut x = t.clone();
+        while x != one {
+            x = field.mul(&x, &x);
+            i += 1;
+        }
+        let mut b = c;
+        for _ in 0..m - i - 1 {
+            b = field.mul(&b, &b);
+        }
+        r = field.mul(&r, &b);
+        c = field.mul(&b, &b);
+        t = field.mul(&t, &c);
+        m = i;
+    }
+    field.to_normal(&r)
+}
+
+/// Square root of the quadratic residue `a` modulo the odd prime `p`, computing `(w + √(w² - a))^((p + 1) / 2)`
+fn cipolla<T: ModularSquareRoot>(a: &T, p: &T) -> T {
+    let mut w = T::from(0);
+    let d = loop {
+        let d = sub_mod(&w.mul_mod(&w, p), a, p);
+        if d.clone().jacobi(p.clone()) == -1 {
+            break d;
+        }
+        w = w + T::from(1);
+    };
+    let field = T::montgomery_field(p);
+    let d = field.to_montgomery(&d);
+    // Elements x + y√d of the quadratic extension, in montgomery form
+    let mul = |(x1, y1): &(T, T), (x2, y2): &(T, T)| {
+        (
+            add_mod(&field.mul(x1, x2), &field.mul(&field.mul(y1, y2), &d), p),
+            add_mod(&field.mul(x1, y2), &field.mul(x2, y1), p),
+        )
+    };
+    let mut exponent = (p.clone() + T::from(1)) / T::from(2);
+    let mut base = (field.to_montgomery(&w), field.to_montgomery(&T::from(1)));
+    let mut result = (field.to_montgomery(&T::from(1)), T::from(0));
+    while exponent > T::from(0) {
+        if exponent.clone() % T::from(2) == T::from(1) {
+            result = mul(&result, &base);
+        }
+        base = mul(&base, &base);
+        exponent = exponent / T::from(2);
+    }
+    field.to_normal(&result.0)
+}
+
+/// Square roots of the unit `a` modulo `p^e`, lifted from a root modulo `p`
+fn unit_roots<T: ModularSquareRoot>(a: &T, p: &T, e: u32) -> Option<Vec<T>> {
+    let prime_power = power(p.clone(), e);
+    let a = a.clone() % prime_power.clone();
+    if *p == T::from(2) {
+        return two_power_unit_roots(&a, e);
+    }
+    let mut r = a.clone().sqrt_mod_prime(p.clone())?;
+    let mut modulus = p.clone();
+    for k in 2..=e {
+        // Newton step r - (r² - a) / 2r modulo p^k
+        modulus = modulus * p.clone();
+        let phi = power(p.clone(), k - 1) * (p.clone() - T::from(1));
+        let inverse = (r.clone() * T::from(2)).power_mod(&(phi - T::from(1)), &modulus);
+        let error = sub_mod(
+            &r.mul_mod(&r, &modulus),
+            &(a.clone() % modulus.clone()),
+            &modulus,
+        );
+        r = sub_mod(&r, &error.mul_mod(&inverse, &modulus), &modulus);
+    }
+    let mut roots = vec![r.clone(), prime_power - r];
+    roots.sort_unstable();
+    Some(roots)
+}
+
+/// Square roots of the odd `a` modulo `2^e`
+fn two_power_unit_roots<T: MultiplicativeOrder>(a: &T, e: u32) -> Option<Vec<T>> {
+    let modulus = power(T::from(2), e);
+    let mut roots = match e {
+        1 => vec![T::from(1)],
+        2 if a.clone() % T::from(4) == T::from(1) => vec![T::from(1), T::from(3)],
+        2 => return None,
+        _ => {
+            if a.clone() % T::from(8) != T::from(1) {
+                return None;
+            }
+            // Fix one bit of the root for every further power of two
+            let mut r = T::from(1);
+            for k in 3..e {
+                let next_modulus = power(T::from(2), k + 1);
+                if r.mul_mod(&r, &next_modulus) != a.clone() % next_modulus {
+                    r = r + power(T::from(2), k - 1);
+                }
+            }
+            let half = modulus.clone() / T::from(2);
+            let r = r % modulus.clone();
+            let other = modulus.clone() - r.clone();
+            vec![
+                r.clone(),
+                other.clone(),
+                (r + half.clone()) % modulus.clone(),
+                (other + half) % modulus,
+            ]
+        }
+    };
+    roots.sort_unstable();
+    roots.dedup();
+    Some(roots)
+}
+
+/// Square roots of `a` modulo `p^e`, handling the powers of `p` dividing `a`
+fn prime_power_roots<T: ModularSquareRoot>(a: &T, p: &T, e: u32) -> Option<PrimePowerRoots<T>> {
+    let prime_power = power(p.clone(), e);
+    let mut a = a.clone() % prime_power;
+    if a == T::from(0) {
+        return Some(PrimePowerRoots {
+            bases: vec![T::from(0)],
+            step: power(p.clone(), e.div_ceil(2)),
+        });
+    }
+    let mut v = 0;
+    while a.clone() % p.clone() == T::from(0) {
+        a = a / p.clone();
+        v += 1;
+    }
+    if v % 2 == 1 {
+        return None;
+    }
+    let root_factor = power(p.clone(), v / 2);
+    let bases = unit_roots(&a, p, e - v)?
+        .into_iter()
+        .map(|r| r * root_factor.clone())
+        .collect();
+    Some(PrimePowerRoots {
+        bases,
+        step: power(p.clone(), e - v / 2),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cipolla, tonelli_shanks, ModularSquareRoot};
+    use crate::arithmetic::MultiplicativeOrder;
+    use crate::{Factorization, Integer};
+
+    #[test]
+    fn symbols() {
+        for n in (1..100u64).step_by(2) {
+            for a in 0..100u64 {
+                // Euler's criterion for primes, multiplicativity for composites
+                let expected: i8 = Factorization::from_primes(crate::Factoring::factor(n))
+                    .factors()
+                    .iter()
+                    .map(|&(p, e)| {
+                        let legendre: i8 = match (
+                            a % p,
+                            crate::arithmetic::MultiplicativeOrder::power_mod(
+                                &a,
+                                &((p - 1) / 2),
+                                &p,
+                            ),
+                        ) {
+                            (0, _) => 0,
+                            (_, 1) => 1,
+                            _ => -1,
+                        };
+                        legendre.pow(e)
+                    })
+                    .product();
+                assert_eq!(a.jacobi(n), expected, "({a} / {n})");
+                assert_eq!(a.kronecker(n), expected);
+            }
+        }
+        assert_eq!(3u64.kronecker(8), -1);
+        assert_eq!(7u64.kronecker(8), 1);
+        assert_eq!(2u64.kronecker(6), 0);
+        assert_eq!(5u64.kronecker(12), -1);
+        assert_eq!(1u64.kronecker(0), 1);
+        assert_eq!(2u64.kronecker(0), 0);
+        for n in (1..50i64).step_by(2) {
+            for a in -100..0i64 {
+                let expected = Integer::from(a + 100 * n).jacobi(Integer::from(n));
+                assert_eq!(Integer::from(a).jacobi(Integer::from(n)), expected);
+                assert_eq!(Integer::from(a).kronecker(Integer::from(n)), expected);
+                assert_eq!(
+                    Integer::from(a).kronecker(Integer::from(2 * n)),
+                    Integer::from(a + 800 * n).kronecker(Integer::from(2 * n)),
+                );
+            }
+        }
+        assert_eq!(Integer::from(-1).jacobi(Integer::from(3)), -1);
+        assert_eq!(Integer::from(-3).kronecker(Integer::from(2)), -1);
+    }
+
+    #[test]
+    fn all_roots() {
+        for n in 1..150u64 {
+            for a in 0..n {
+                let expected: Vec<u64> = (0..n).filter(|x| x * x % n == a).collect();
+                assert_eq!(a.sqrt_mod_all(n), expected, "sqrt({a}) mod {n}");
+                let root = a.sqrt_mod(n);
+                assert_eq!(root.is_some(), !expected.is_empty());
+                if let Some(root) = root {
+                    assert!(expected.contains(&root), "sqrt({}) mod {}", a, n);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn algorithms() {
+        // p - 1 = 2^32 * (2^32 - 1), so cipolla's algorithm is chosen
+        let p = (1u128 << 64) - (1 << 32) + 1;
+        for a in [2u128, 3, 10, 1 << 63, p - 1] {
+            let square = a * a % p;
+            let expected = a.min(p - a);
+            assert_eq!(square.sqrt_mod_prime(p), Some(expected));
+            let ts = tonelli_shanks(&square, &p);
+            let c = cipolla(&square, &p);
+            assert!(ts == a || ts == p - a);
+            assert!(c == a || c == p - a);
+        }
+        assert_eq!(7u128.sqrt_mod_prime(p), None);
+        let p = Integer::from(p);
+        let square = Integer::from(10 * 10);
+        for root in [tonelli_shanks(&square, &p), cipolla(&square, &p)] {
+            assert!(root == 10 || root == p.clone() - 10u32);
+        }
+    }
+
+    #[test]
+    fn near_maximum() {
+        // The goldilocks prime uses cipolla's algorithm, the largest prime below 2^64 takes the shortcut for p ≡ 3 (mod 4)
+        for p in [0xFFFF_FFFF_0000_0001, 18_446_744_073_709_551_557u64] {
+            for a in [2u64, 3, 10, 1 << 62, 1 << 63, p - 1] {
+                let square = a.mul_mod(&a, &p);
+                assert_eq!(square.sqrt_mod_prime(p), Some(a.min(p - a)));
+                let root = square.sqrt_mod(p).unwrap();
+                assert!(root == a || root == p - a, "sqrt({}) mod {}", square, p);
+                assert_eq!(square.sqrt_mod_all(p), {
+                    let mut roots = vec![a, p - a];
+                    roots.sort_unstable();
+                    roots
+                });
+            }
+        }
+        let p = 0xFFFF_FFFF_0000_0001u64;
+        let square = 10u64.mul_mod(&10, &p);
+        assert!([10, p - 10].contains(&tonelli_shanks(&square, &p)));
+        assert!([10, p - 10].contains(&cipolla(&square, &p)));
+    }
+
+    #[test]
+    fn prime_powers() {
+        let p = 1_000_000_007u64;
+        let n = Integer::from(p).square() * Integer::from(Integer::u_pow_u(3, 7)) * 1024u32;
+        let x = Integer::from(123_456_789_123_456_789_u64);
+        let square = x.square() % &n;
+        let root = square.clone().sqrt_mod(n.clone()).unwrap();
+        assert_eq!(root.square() % &n, square);
+        // 2 roots modulo p^2 and 3^7 each, 4 modulo 2^10
+        let small = Integer::from(11 * 11);
+        assert_eq!(small.sqrt_mod_all(n).len(), 16);
+    }
+}
@@ -6,22 +6,50 @@ pub trait MapFunction<T> {
     fn run(&mut self, n: T) -> T;
 }
 
-pub fn find_cycle<T, CountType, Mapper, ConditionChecker>(
+/// Decides when a long running iteration has to be aborted
+pub trait IterationLimit {
+    /// Account for one more iteration, returns `true` if the iteration has to be aborted
+    fn step(&mut self) -> bool;
+    /// Whether the limit has been reached, without accounting for another iteration
+    fn exhausted(&self) -> bool;
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+/// Limit which is never reached
+pub struct NoIterationLimit;
+
+impl IterationLimit for NoIterationLimit {
+    fn step(&mut self) -> bool {
+        false
+    }
+
+    fn exhausted(&self) -> bool {
+        false
+    }
+}
+
+/// Returns `None` if `limit` has been exhausted before a cycle was found
+pub fn find_cycle<T, CountType, Mapper, ConditionChecker, Limit>(
     mut mapper: Mapper,
     mut cycle_condition: ConditionChecker,
     start: T,
-) -> (ConditionChecker, CountType)
+    limit: &mut Limit,
+) -> Option<(ConditionChecker, CountType)>
 where
     T: Clone + std::fmt::Debug,
     CountType: num_traits::PrimInt + std::ops::ShlAssign + std::ops::AddAssign,
     Mapper: MapFunction<T>,
     ConditionChecker: CycleConditionChecker<T, CountType>,
+    Limit: IterationLimit + ?Sized,
 {
     let mut tortoise = start.clone();
     let mut hare = mapper.run(start);
     let mut power = CountType::one();
     let mut count = CountType::zero();
     while !cycle_condition.check(&tortoise, &hare, &count, &power) {
+        if limit.step() {
+            return None;
+        }
         count += CountType::one();
         if power == count {
             tortoise = hare.clone();
@@ -30,5 +58,5 @@ where
         }
         hare = mapper.run(hare);
     }
-    (cycle_condition, count)
+    Some((cycle_condition, count))
 }
@@ -2,5 +2,8 @@ mod brent_cycle;
 mod pollard_rho;
 mod trial_division;
 
-pub use pollard_rho::PollardRho;
+pub(crate) use brent_cycle::{find_cycle, CycleConditionChecker, MapFunction};
+pub use brent_cycle::{IterationLimit, NoIterationLimit};
+pub(crate) use pollard_rho::resumable_rug_rho;
+pub use pollard_rho::{PollardRho, PollardRhoProgress, PollardRhoStatistics};
 pub use trial_division::TrialDivision;
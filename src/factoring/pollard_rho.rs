@@ -2,9 +2,18 @@ use redc::Redc;
 use redc::{self, Field};
 use twoword::TwoWord;
 
-use crate::util::NumUtil;
+use crate::util::{BitLength, NumUtil};
 
-use super::brent_cycle::find_cycle;
+use super::brent_cycle::{find_cycle, IterationLimit, NoIterationLimit};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+/// Effort spent by a single run of [`PollardRho`]
+pub struct PollardRhoStatistics {
+    /// Number of elements of the series `x` = `x` * `x` + `increment` which have been generated
+    pub iterations: u64,
+    /// Number of greatest common divisors which have been computed
+    pub gcds: u64,
+}
 
 /// Find factors of given number by applying Pollard's rho algorithm using Brent's cycle detection
 ///
@@ -17,7 +26,86 @@ pub trait PollardRho: Sized {
     ///
     /// # Returns
     /// A factor if one has been found, or `None` if the algorithm was unsuccessful
-    fn pollard_rho(self, start: &Self, increment: &Self) -> Option<Self>;
+    fn pollard_rho(self, start: &Self, increment: &Self) -> Option<Self> {
+        self.pollard_rho_statistics(start, increment).0
+    }
+
+    /// Same as [`PollardRho::pollard_rho`], but also reports the effort spent
+    ///
+    /// # Example
+    /// ```
+    /// use facto::factoring::PollardRho;
+    /// let (factor, statistics) = (4_294_967_279u64 * 4_294_967_291).pollard_rho_statistics(&2, &1);
+    /// assert!(factor == Some(4_294_967_279) || factor == Some(4_294_967_291));
+    /// assert!(statistics.iterations > 0 && statistics.gcds > 0);
+    /// ```
+    fn pollard_rho_statistics(
+        self,
+        start: &Self,
+        increment: &Self,
+    ) -> (Option<Self>, PollardRhoStatistics) {
+        self.pollard_rho_limited(start, increment, &mut NoIterationLimit)
+    }
+
+    /// Same as [`PollardRho::pollard_rho_statistics`], but gives up once `limit` is exhausted
+    ///
+    /// # Example
+    /// ```
+    /// use facto::factoring::{IterationLimit, PollardRho};
+    /// struct Steps(u64);
+    /// impl IterationLimit for Steps {
+    ///     fn step(&mut self) -> bool {
+    ///         self.0 = self.0.saturating_sub(1);
+    ///         self.exhausted()
+    ///     }
+    ///     fn exhausted(&self) -> bool {
+    ///         self.0 == 0
+    ///     }
+    /// }
+    /// let n = 4_294_967_279u64 * 4_294_967_291;
+    /// let mut limit = Steps(10);
+    /// assert_eq!(n.pollard_rho_limited(&2, &1, &mut limit).0, None);
+    /// assert!(limit.exhausted());
+    /// ```
+    fn pollard_rho_limited(
+        self,
+        start: &Self,
+        increment: &Self,
+        limit: &mut dyn IterationLimit,
+    ) -> (Option<Self>, PollardRhoStatistics);
+}
+
+/// Run pollard's rho inside a span, if the `tracing` feature is enabled
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+fn traced_rho<T: std::fmt::Display>(
+    bits: u32,
+    start: &T,
+    increment: &T,
+    rho: impl FnOnce() -> (Option<T>, PollardRhoStatistics),
+) -> (Option<T>, PollardRhoStatistics) {
+    enter_span!(
+        span,
+        "pollard_rho",
+        bits,
+        seed = %start,
+        increment = %increment,
+        iterations = tracing::field::Empty,
+        gcds = tracing::field::Empty,
+        outcome = tracing::field::Empty,
+    );
+    let result = rho();
+    record_span!(span, "iterations", result.1.iterations);
+    record_span!(span, "gcds", result.1.gcds);
+    record_span!(
+        span,
+        "outcome",
+        if result.0.is_some() {
+            "factor"
+        } else {
+            "failed"
+        }
+    );
+    result
 }
 
 struct PollardRhoCycleConditionCheckerU64 {
@@ -26,9 +114,12 @@ struct PollardRhoCycleConditionCheckerU64 {
     n: u64,
     last_tortoise: u64,
     last_hare: u64,
+    statistics: PollardRhoStatistics,
 }
 
-impl super::brent_cycle::CycleConditionChecker<u64, u64> for PollardRhoCycleConditionCheckerU64 {
+impl super::brent_cycle::CycleConditionChecker<u64, u64>
+    for &mut PollardRhoCycleConditionCheckerU64
+{
     fn check(&mut self, tortoise: &u64, hare: &u64, count: &u64, power: &u64) -> bool {
         let diff = if hare > tortoise {
             hare - tortoise
@@ -40,7 +131,9 @@ impl super::brent_cycle::CycleConditionChecker<u64, u64> for PollardRhoCycleCond
         debug_assert_eq!(power.count_ones(), 1);
         let power_count = power.trailing_zeros();
 
+        self.statistics.iterations += 1;
         if (power - count) & ((1 << std::cmp::max(5, power_count / 2)) - 1) == 1 {
+            self.statistics.gcds += 1;
             let d = u64::gcd(self.accum.to_normal(&self.field), self.n);
             if d != 1 {
                 return true;
@@ -59,11 +152,14 @@ impl PollardRhoCycleConditionCheckerU64 {
             n,
             last_tortoise: start,
             last_hare: start,
+            statistics: PollardRhoStatistics::default(),
         }
     }
-    fn extract(self, mut f: PollardRhoMapperU64) -> u64 {
+    fn extract(mut self, mut f: PollardRhoMapperU64) -> (u64, PollardRhoStatistics) {
         let mut hare = super::brent_cycle::MapFunction::run(&mut f, self.last_hare);
         loop {
+            self.statistics.iterations += 1;
+            self.statistics.gcds += 1;
             let x_minus_y_abs = if hare > self.last_tortoise {
                 hare - self.last_tortoise
             } else {
@@ -71,7 +167,7 @@ impl PollardRhoCycleConditionCheckerU64 {
             };
             let d = u64::gcd(x_minus_y_abs.to_normal(&self.field), self.n);
             if d != 1 {
-                return d;
+                return (d, self.statistics);
             }
             hare = super::brent_cycle::MapFunction::run(&mut f, hare);
         }
@@ -92,9 +188,12 @@ struct PollardRhoCycleConditionCheckerU128 {
     n: u128,
     last_tortoise: u128,
     last_hare: u128,
+    statistics: PollardRhoStatistics,
 }
 
-impl super::brent_cycle::CycleConditionChecker<u128, u128> for PollardRhoCycleConditionCheckerU128 {
+impl super::brent_cycle::CycleConditionChecker<u128, u128>
+    for &mut PollardRhoCycleConditionCheckerU128
+{
     #[inline]
     fn check(&mut self, tortoise: &u128, hare: &u128, count: &u128, power: &u128) -> bool {
         let diff = if hare > tortoise {
@@ -107,7 +206,9 @@ impl super::brent_cycle::CycleConditionChecker<u128, u128> for PollardRhoCycleCo
         debug_assert_eq!(power.count_ones(), 1);
         let power_count = power.trailing_zeros();
 
+        self.statistics.iterations += 1;
         if (power - count) & ((1 << std::cmp::max(5, power_count / 2)) - 1) == 1 {
+            self.statistics.gcds += 1;
             let d = u128::gcd(self.accum.to_normal(&self.field), self.n);
             if d != 1 {
                 return true;
@@ -126,11 +227,14 @@ impl PollardRhoCycleConditionCheckerU128 {
             n,
             last_tortoise: start,
             last_hare: start,
+            statistics: PollardRhoStatistics::default(),
         }
     }
-    fn extract(self, mut f: PollardRhoMapperU128) -> u128 {
+    fn extract(mut self, mut f: PollardRhoMapperU128) -> (u128, PollardRhoStatistics) {
         let mut hare = super::brent_cycle::MapFunction::run(&mut f, self.last_hare);
         loop {
+            self.statistics.iterations += 1;
+            self.statistics.gcds += 1;
             let x_minus_y_abs = if hare > self.last_tortoise {
                 hare - self.last_tortoise
             } else {
@@ -138,7 +242,7 @@ impl PollardRhoCycleConditionCheckerU128 {
             };
             let d = u128::gcd(x_minus_y_abs.to_normal(&self.field), self.n);
             if d != 1 {
-                return d;
+                return (d, self.statistics);
             }
             hare = super::brent_cycle::MapFunction::run(&mut f, hare);
         }
@@ -164,6 +268,7 @@ struct PollardRhoCycleConditionCheckerRug {
     n: rug::Integer,
     last_tortoise: rug::Integer,
     last_hare: rug::Integer,
+    statistics: PollardRhoStatistics,
 }
 
 impl PollardRhoCycleConditionCheckerRug {
@@ -185,7 +290,9 @@ impl PollardRhoCycleConditionCheckerRug {
         debug_assert_eq!(power.count_ones(), Some(1));
         let power_count = power.find_one(0).unwrap();
 
+        self.statistics.iterations += 1;
         if (power.clone() - count).keep_bits(std::cmp::max(4, power_count / 2)) == 1 {
+            self.statistics.gcds += 1;
             let d = self.accum.clone().to_normal(&self.field).gcd(&self.n);
             if d != 1 {
                 return true;
@@ -205,15 +312,18 @@ impl PollardRhoCycleConditionCheckerRug {
             n,
             last_tortoise: start.clone(),
             last_hare: start,
+            statistics: PollardRhoStatistics::default(),
         }
     }
     fn extract(
-        self,
+        mut self,
         increment: &rug::Integer,
         field: &<rug::Integer as Redc>::FieldType,
-    ) -> rug::Integer {
+    ) -> (rug::Integer, PollardRhoStatistics) {
         let mut hare: rug::Integer = field.redc(self.last_hare.square() + increment);
         loop {
+            self.statistics.iterations += 1;
+            self.statistics.gcds += 1;
             let x_minus_y_abs: rug::Integer = if hare > self.last_tortoise {
                 hare.clone() - &self.last_tortoise
             } else {
@@ -221,7 +331,7 @@ impl PollardRhoCycleConditionCheckerRug {
             };
             let d = x_minus_y_abs.to_normal(&self.field).gcd(&self.n);
             if d != 1 {
-                return d;
+                return (d, self.statistics);
             }
             hare = field.redc(hare.square() + increment);
         }
@@ -229,81 +339,171 @@ impl PollardRhoCycleConditionCheckerRug {
 }
 
 impl PollardRho for u64 {
-    fn pollard_rho(self, start: &Self, constant_increment: &Self) -> Option<Self> {
-        let field = self.setup_field();
-        let start = start.to_montgomery(&field);
-        let constant_increment = constant_increment.to_montgomery(&field);
-        let (e, _) = find_cycle::<_, Self, _, _>(
-            PollardRhoMapperU64(constant_increment, field.clone()),
-            PollardRhoCycleConditionCheckerU64::new(&field, self, start),
-            start,
-        );
-        let d = e.extract(PollardRhoMapperU64(constant_increment, field));
-        if d == self {
-            None
-        } else {
-            Some(d)
-        }
+    fn pollard_rho_limited(
+        self,
+        start: &Self,
+        constant_increment: &Self,
+        limit: &mut dyn IterationLimit,
+    ) -> (Option<Self>, PollardRhoStatistics) {
+        traced_rho(self.bit_length(), start, constant_increment, || {
+            let field = self.setup_field();
+            let start = start.to_montgomery(&field);
+            let constant_increment = constant_increment.to_montgomery(&field);
+            let mut checker = PollardRhoCycleConditionCheckerU64::new(&field, self, start);
+            if find_cycle::<_, Self, _, _, _>(
+                PollardRhoMapperU64(constant_increment, field.clone()),
+                &mut checker,
+                start,
+                limit,
+            )
+            .is_none()
+            {
+                return (None, checker.statistics);
+            }
+            let (d, statistics) = checker.extract(PollardRhoMapperU64(constant_increment, field));
+            if d == self {
+                (None, statistics)
+            } else {
+                (Some(d), statistics)
+            }
+        })
     }
 }
 
 impl PollardRho for u128 {
-    fn pollard_rho(self, start: &Self, constant_increment: &Self) -> Option<Self> {
-        let field = self.setup_field();
-        let start = start.to_montgomery(&field);
-        let constant_increment = constant_increment.to_montgomery(&field);
-        let (e, _) = find_cycle::<_, Self, _, _>(
-            PollardRhoMapperU128(constant_increment, field.clone()),
-            PollardRhoCycleConditionCheckerU128::new(&field, self, start),
-            start,
-        );
-        let d = e.extract(PollardRhoMapperU128(constant_increment, field));
-        if d == self {
-            None
-        } else {
-            Some(d)
-        }
+    fn pollard_rho_limited(
+        self,
+        start: &Self,
+        constant_increment: &Self,
+        limit: &mut dyn IterationLimit,
+    ) -> (Option<Self>, PollardRhoStatistics) {
+        traced_rho(self.bit_length(), start, constant_increment, || {
+            let field = self.setup_field();
+            let start = start.to_montgomery(&field);
+            let constant_increment = constant_increment.to_montgomery(&field);
+            let mut checker = PollardRhoCycleConditionCheckerU128::new(&field, self, start);
+            if find_cycle::<_, Self, _, _, _>(
+                PollardRhoMapperU128(constant_increment, field.clone()),
+                &mut checker,
+                start,
+                limit,
+            )
+            .is_none()
+            {
+                return (None, checker.statistics);
+            }
+            let (d, statistics) = checker.extract(PollardRhoMapperU128(constant_increment, field));
+            if d == self {
+                (None, statistics)
+            } else {
+                (Some(d), statistics)
+            }
+        })
     }
 }
 
-fn find_rug_cycle(
-    mut cycle_condition: PollardRhoCycleConditionCheckerRug,
-    start: rug::Integer,
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// State of an interrupted run of [`PollardRho`] on a [`rug::Integer`], from which the run can be resumed
+///
+/// Elements of the series are stored in montgomery representation
+pub struct PollardRhoProgress {
+    pub(crate) tortoise: rug::Integer,
+    pub(crate) hare: rug::Integer,
+    pub(crate) power: rug::Integer,
+    pub(crate) count: rug::Integer,
+    pub(crate) accum: rug::Integer,
+    pub(crate) last_tortoise: rug::Integer,
+    pub(crate) last_hare: rug::Integer,
+    pub(crate) statistics: PollardRhoStatistics,
+}
+
+impl PollardRhoProgress {
+    #[must_use]
+    /// Effort spent by the run so far
+    pub const fn statistics(&self) -> &PollardRhoStatistics {
+        &self.statistics
+    }
+}
+
+/// Run pollard's rho on `n`, continuing from `progress` if it is set
+///
+/// If `limit` is exhausted before a cycle was found, `progress` is set to the state of the run
+pub fn resumable_rug_rho(
+    n: &rug::Integer,
+    start: &rug::Integer,
     increment: &rug::Integer,
-    field: &<rug::Integer as Redc>::FieldType,
-) -> PollardRhoCycleConditionCheckerRug {
-    let mut tortoise = start.clone();
-    let mut hare = field.redc(start.square() + increment);
-    let mut power = rug::Integer::from(1);
-    let mut count = rug::Integer::from(0);
-    while !cycle_condition.check(&tortoise, &hare, &count, &power) {
+    progress: &mut Option<PollardRhoProgress>,
+    limit: &mut dyn IterationLimit,
+) -> (Option<rug::Integer>, PollardRhoStatistics) {
+    let field = n.clone().setup_field();
+    let increment = increment.clone().to_montgomery(&field);
+    let (mut checker, mut tortoise, mut hare, mut power, mut count) =
+        if let Some(p) = progress.take() {
+            (
+                PollardRhoCycleConditionCheckerRug {
+                    field: field.clone(),
+                    accum: p.accum,
+                    n: n.clone(),
+                    last_tortoise: p.last_tortoise,
+                    last_hare: p.last_hare,
+                    statistics: p.statistics,
+                },
+                p.tortoise,
+                p.hare,
+                p.power,
+                p.count,
+            )
+        } else {
+            let start = start.clone().to_montgomery(&field);
+            let hare = field.redc(start.clone().square() + &increment);
+            (
+                PollardRhoCycleConditionCheckerRug::new(&field, n.clone(), start.clone()),
+                start,
+                hare,
+                rug::Integer::from(1),
+                rug::Integer::from(0),
+            )
+        };
+    while !checker.check(&tortoise, &hare, &count, &power) {
         count += 1;
         if power == count {
             tortoise = hare.clone();
             power <<= 1;
             count = rug::Integer::from(0);
         }
-        hare = field.redc(hare.square() + increment);
+        hare = field.redc(hare.square() + &increment);
+        if limit.step() {
+            let statistics = checker.statistics;
+            *progress = Some(PollardRhoProgress {
+                tortoise,
+                hare,
+                power,
+                count,
+                accum: checker.accum,
+                last_tortoise: checker.last_tortoise,
+                last_hare: checker.last_hare,
+                statistics,
+            });
+            return (None, statistics);
+        }
+    }
+    let (d, statistics) = checker.extract(&increment, &field);
+    if &d == n {
+        (None, statistics)
+    } else {
+        (Some(d), statistics)
     }
-    cycle_condition
 }
 
 impl PollardRho for rug::Integer {
-    fn pollard_rho(self, start: &Self, constant_increment: &Self) -> Option<Self> {
-        let field = self.clone().setup_field();
-        let start = start.clone().to_montgomery(&field);
-        let constant_increment = constant_increment.clone().to_montgomery(&field);
-        let e = find_rug_cycle(
-            PollardRhoCycleConditionCheckerRug::new(&field, self.clone(), start.clone()),
-            start,
-            &constant_increment,
-            &field,
-        );
-        let d = e.extract(&constant_increment, &field);
-        if d == self {
-            None
-        } else {
-            Some(d)
-        }
+    fn pollard_rho_limited(
+        self,
+        start: &Self,
+        constant_increment: &Self,
+        limit: &mut dyn IterationLimit,
+    ) -> (Option<Self>, PollardRhoStatistics) {
+        traced_rho(self.bit_length(), start, constant_increment, || {
+            resumable_rug_rho(&self, start, constant_increment, &mut None, limit)
+        })
     }
 }
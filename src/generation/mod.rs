@@ -0,0 +1,10 @@
+mod provable;
+mod random;
+mod special;
+
+pub use provable::provable_prime;
+pub use random::{random_prime, PrimeGenerator, RandomSource, RngCoreSource, TopBits};
+pub use special::{
+    is_dsa_pair, is_safe_prime, is_sophie_germain_prime, is_strong_prime, DsaPrimes, SafePrime,
+    StrongPrime,
+};
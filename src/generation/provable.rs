@@ -0,0 +1,170 @@
+use std::slice;
+
+use rug::Integer;
+
+use super::random::{PrimeGenerator, RandomSource};
+use crate::optimized_factoring::LucasCertificateTrait;
+use crate::primality::{
+    LucasPrimality, LucasPrimalityResult, MillerRabin, MillerRabinCompositeResult,
+};
+use crate::{
+    CertificateElementKind, CertifiedFactorization, LucasCertificate, LucasCertificateElement,
+    PrimalityCertainty,
+};
+
+/// Bit length up to which primes are drawn at random and certified by factoring p - 1
+const BASE_BITS: u32 = 64;
+
+/// Smallest bit length left to the random cofactor R of the candidates 2Rq + 1
+const MIN_COFACTOR_BITS: u32 = 20;
+
+/// Exclusive bound of the bases tried for pocklington's criterion
+const MAX_BASE: u32 = 100;
+
+/// Random prime with exactly `bits` bits, together with a certificate of its primality, using Maurer's algorithm
+///
+/// A prime q larger than the square root of 2^`bits` is generated recursively, with a random relative size as proposed by Maurer.
+/// The candidates n = 2Rq + 1 for random R are then proven prime by pocklington's criterion, only knowing the factor q of n - 1,
+/// see [`CertificateElementKind::Pocklington`]. Primes up to 64 bits are certified by factoring p - 1.
+///
+/// # Panics
+/// If `bits` is below 2
+///
+/// # Example
+/// ```
+/// use facto::generation::provable_prime;
+/// use facto::LucasCertificateElement;
+/// use rug::rand::RandState;
+/// let mut rng = RandState::new();
+/// let (p, certificate) = provable_prime(512, &mut rng);
+/// assert_eq!(p.significant_bits(), 512);
+/// assert_eq!(certificate.get_max().unwrap().n, p);
+/// assert!(certificate.elements.iter().all(LucasCertificateElement::verify));
+/// assert!(certificate.dangling().is_empty());
+/// ```
+pub fn provable_prime(
+    bits: u32,
+    rng: &mut (impl RandomSource + ?Sized),
+) -> (Integer, LucasCertificate<Integer>) {
+    assert!(bits >= 2, "Primes have at least 2 bits");
+    let mut certificate = LucasCertificate::default();
+    let p = maurer(bits, rng, &mut certificate);
+    (p, certificate)
+}
+
+/// Certificate element proving `n` prime by pocklington's criterion, for a prime `factor` of n - 1 exceeding the square root of n
+///
+/// # Returns
+/// `None` if `n` is composite, or none of the small bases satisfies the criterion
+pub(super) fn pocklington_element(
+    n: &Integer,
+    factor: &Integer,
+) -> Option<LucasCertificateElement<Integer>> {
+    for base in 2..MAX_BASE {
+        let base = Integer::from(base);
+        match n
+            .clone()
+            .pocklington_test(slice::from_ref(factor), base.clone())
+        {
+            LucasPrimalityResult::Prime => {
+                return Some(LucasCertificateElement {
+                    n: n.clone(),
+                    base,
+                    unique_prime_divisors: vec![factor.clone()],
+                    kind: CertificateElementKind::Pocklington,
+                })
+            }
+            LucasPrimalityResult::Composite => return None,
+            LucasPrimalityResult::Unknown => {}
+        }
+    }
+    None
+}
+
+/// Generate a prime with `bits` bits, pushing the elements proving its primality to `certificate`
+#[allow(
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
+fn maurer<R: RandomSource + ?Sized>(
+    bits: u32,
+    rng: &mut R,
+    certificate: &mut LucasCertificate<Integer>,
+) -> Integer {
+    if bits <= BASE_BITS {
+        let p: Integer = PrimeGenerator::new(bits)
+            .generate(rng)
+            .unwrap_or_else(|| unreachable!());
+        assert!(
+            p.clone()
+                .certified_prime_check(PrimalityCertainty::Certified(certificate)),
+            "Generated composite {}",
+            p
+        );
+        return p;
+    }
+    // q has more than half the bits, so it exceeds the square root of n
+    let min_bits = bits.div_ceil(2) + 1;
+    let max_bits = bits - MIN_COFACTOR_BITS;
+    loop {
+        // Relative size 2^(s - 1) for a uniformly random s in [0, 1)
+        let s = rng.random_below(&(Integer::from(1) << 32u32)).to_f64() / 2f64.powi(32);
+        let relative = (s - 1.0).exp2();
+        let q_bits = ((f64::from(bits) * relative) as u32).clamp(min_bits, max_bits);
+        let mut q_certificate = LucasCertificate::default();
+        let q = maurer(q_bits, rng, &mut q_certificate);
+        let generator = PrimeGenerator::new(bits).congruent(1, Integer::from(&q * 2u32));
+        let found = generator.search(rng, &[], |candidate, _| {
+            if candidate.clone().miller_rabin(Integer::from(2))
+                == MillerRabinCompositeResult::Composite
+            {
+                return None;
+            }
+            pocklington_element(candidate, &q)
+        });
+        // Without any prime in the progression, start over with another q
+        if let Some(element) = found {
+            for e in q_certificate.elements {
+                certificate.push(e);
+            }
+            let n = element.n.clone();
+            certificate.push(element);
+            return n;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::provable_prime;
+    use crate::{CertificateElementKind, LucasCertificateElement};
+    use rug::rand::RandState;
+    use std::slice;
+
+    #[test]
+    fn certificates() {
+        let mut rng = RandState::new();
+        for bits in (2..=200).chain([521, 1024]) {
+            let (p, certificate) = provable_prime(bits, &mut rng);
+            assert_eq!(p.significant_bits(), bits);
+            assert!(
+                certificate
+                    .elements
+                    .iter()
+                    .all(LucasCertificateElement::verify),
+                "{:?}",
+                certificate
+            );
+            assert!(certificate.dangling().is_empty());
+            let roots: Vec<_> = certificate.roots().map(|e| e.n.clone()).collect();
+            assert_eq!(roots, slice::from_ref(&p));
+            let kind = certificate.element(&p).unwrap().kind;
+            if bits > 64 {
+                assert_eq!(kind, CertificateElementKind::Pocklington);
+            } else {
+                assert_eq!(kind, CertificateElementKind::Lucas);
+            }
+        }
+    }
+}
@@ -0,0 +1,453 @@
+use std::convert::TryFrom;
+
+use rand_core::RngCore;
+use rug::integer::Order;
+use rug::rand::RandState;
+use rug::Integer;
+
+use crate::primality::{MillerRabin, MillerRabinCompositeResult};
+use crate::sieve::primes_in;
+use crate::util::inverse_mod;
+use crate::{LucasCertificate, Primality};
+
+/// Exclusive bound of the primes candidates are sieved by
+const SIEVE_BOUND: u64 = 1 << 12;
+
+/// Random Miller-Rabin bases checked for candidates above 2^64, bounding the error probability by 4^-64
+const MILLER_RABIN_ROUNDS: u32 = 64;
+
+/// Source of uniformly random numbers, implemented for [`RandState`] and, using [`RngCoreSource`], any [`RngCore`]
+pub trait RandomSource {
+    /// Uniformly random number in 0..`bound`, for a positive `bound`
+    fn random_below(&mut self, bound: &Integer) -> Integer;
+}
+
+impl RandomSource for RandState<'_> {
+    fn random_below(&mut self, bound: &Integer) -> Integer {
+        Integer::from(bound.random_below_ref(self))
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Wrapper making any [`RngCore`] a [`RandomSource`]
+///
+/// # Example
+/// ```
+/// use facto::generation::{random_prime, RngCoreSource};
+/// # struct Counter(u64);
+/// # impl rand_core::RngCore for Counter {
+/// #     fn next_u32(&mut self) -> u32 { self.next_u64() as u32 }
+/// #     fn next_u64(&mut self) -> u64 { self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407); self.0 >> 11 }
+/// #     fn fill_bytes(&mut self, dest: &mut [u8]) { rand_core::impls::fill_bytes_via_next(self, dest) }
+/// #     fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> { Ok(self.fill_bytes(dest)) }
+/// # }
+/// let mut rng = RngCoreSource(Counter(1));
+/// let p: u64 = random_prime(40, &mut rng);
+/// assert_eq!(p >> 39, 1);
+/// ```
+pub struct RngCoreSource<R>(pub R);
+
+impl<R: RngCore> RandomSource for RngCoreSource<R> {
+    fn random_below(&mut self, bound: &Integer) -> Integer {
+        let bits = bound.significant_bits();
+        let mut bytes = vec![0u8; bits.div_ceil(8) as usize];
+        // Rejection sampling, accepting each try with a probability of at least 1/2
+        loop {
+            self.0.fill_bytes(&mut bytes);
+            let mut candidate = Integer::from_digits(&bytes, Order::Lsf);
+            candidate.keep_bits_mut(bits);
+            if candidate < *bound {
+                return candidate;
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Leading bits fixed to one in generated primes
+pub enum TopBits {
+    /// No bits fixed, the prime may have less than the requested bit length
+    Free,
+    /// The top bit is set, so the prime has exactly the requested bit length
+    One,
+    /// The top two bits are set, so the product of two such primes has exactly twice their bit length
+    Two,
+}
+
+#[derive(Debug, Clone)]
+/// Generator of random primes with a bit length, leading bits and a residue modulo some number
+///
+/// A random candidate is drawn, and the candidates following it are sieved incrementally,
+/// keeping their residues modulo the small primes up to date.
+/// Survivors are checked by a Miller-Rabin test to base 2, then by [`Primality::is_prime`] if below 2^64,
+/// or else by Miller-Rabin tests to 64 random bases.
+///
+/// # Example
+/// ```
+/// use facto::generation::{PrimeGenerator, TopBits};
+/// use facto::Integer;
+/// use rug::rand::RandState;
+/// let mut rng = RandState::new();
+/// let generator = PrimeGenerator::new(256).top_bits(TopBits::Two).congruent(3, 4);
+/// let p: Integer = generator.generate(&mut rng).unwrap();
+/// assert_eq!(p.significant_bits(), 256);
+/// assert!(p.get_bit(254));
+/// assert_eq!(p.mod_u(4), 3);
+/// ```
+pub struct PrimeGenerator {
+    bits: u32,
+    top_bits: TopBits,
+    residue: Integer,
+    modulus: Integer,
+}
+
+impl PrimeGenerator {
+    #[must_use]
+    /// Generator of primes with exactly `bits` bits
+    ///
+    /// # Panics
+    /// If `bits` is below 2
+    pub fn new(bits: u32) -> Self {
+        assert!(bits >= 2, "Primes have at least 2 bits");
+        Self {
+            bits,
+            top_bits: TopBits::One,
+            residue: Integer::new(),
+            modulus: Integer::from(1),
+        }
+    }
+
+    #[must_use]
+    /// Fix the leading bits of the generated primes, by default [`TopBits::One`]
+    pub const fn top_bits(mut self, top_bits: TopBits) -> Self {
+        self.top_bits = top_bits;
+        self
+    }
+
+    #[must_use]
+    /// Only generate primes congruent to `residue` modulo `modulus`
+    ///
+    /// # Panics
+    /// If `modulus` is not positive, or `residue` and `modulus` are not coprime
+    pub fn congruent(mut self, residue: impl Into<Integer>, modulus: impl Into<Integer>) -> Self {
+        let modulus = modulus.into();
+        assert!(modulus > 0, "Modulus needs to be positive");
+        let residue = residue.into().div_rem_euc(modulus.clone()).1;
+        assert!(
+            Integer::from(residue.gcd_ref(&modulus)) == 1,
+            "Residue and modulus need to be coprime"
+        );
+        self.residue = residue;
+        self.modulus = modulus;
+        self
+    }
+
+    /// Smallest and largest allowed number
+    fn interval(&self) -> (Integer, Integer) {
+        let high = (Integer::from(1) << self.bits) - 1;
+        let low = match self.top_bits {
+            TopBits::Free => Integer::from(2),
+            TopBits::One => Integer::from(1) << (self.bits - 1),
+            TopBits::Two if self.bits == 2 => Integer::from(3),
+            TopBits::Two => Integer::from(3) << (self.bits - 2),
+        };
+        (low, high)
+    }
+
+    /// Walk the candidates from a random one, wrapping around at the end of the interval,
+    /// and return the first survivor of the sieve accepted by `accept`
+    ///
+    /// Candidates n are also removed if any of the `forms` a * n + b, given by pairs (a, b), has a small factor.
+    pub(super) fn search<R: RandomSource + ?Sized, X>(
+        &self,
+        rng: &mut R,
+        forms: &[(u32, u32)],
+        mut accept: impl FnMut(&Integer, &mut R) -> Option<X>,
+    ) -> Option<X> {
+        let (low, high) = self.interval();
+        // Candidates are residue + modulus * t for t in first..=last
+        let first = (low - &self.residue).div_rem_ceil(self.modulus.clone()).0;
+        let last = (high - &self.residue).div_rem_floor(self.modulus.clone()).0;
+        if first > last {
+            return None;
+        }
+        let count = Integer::from(&last - &first) + 1;
+        let start = rng.random_below(&count) + &first;
+        // Sieving primes with the residues of candidates divisible by them or making a form divisible by them
+        let excluded: Vec<(u32, Vec<u32>)> = primes_in(2..SIEVE_BOUND)
+            .map(|p| u32::try_from(p).unwrap_or_else(|_| unreachable!()))
+            .filter(|p| !self.modulus.is_divisible_u(*p))
+            .map(|p| {
+                let mut residues = vec![0];
+                residues.extend(forms.iter().filter(|(a, _)| a % p != 0).map(|&(a, b)| {
+                    let inverse = inverse_mod(u64::from(a), u64::from(p));
+                    let residue = u64::from((p - b % p) % p) * inverse % u64::from(p);
+                    u32::try_from(residue).unwrap_or_else(|_| unreachable!())
+                }));
+                (p, residues)
+            })
+            .collect();
+        let walks = [(start.clone(), last), (first, start - 1)];
+        for (from, to) in &walks {
+            if from > to {
+                continue;
+            }
+            let mut candidate = Integer::from(&self.modulus * from) + &self.residue;
+            let end = Integer::from(&self.modulus * to) + &self.residue;
+            let mut residues: Vec<(u32, u32)> = excluded
+                .iter()
+                .map(|(p, _)| (candidate.mod_u(*p), self.modulus.mod_u(*p)))
+                .collect();
+            loop {
+                // Candidates below the bound are kept, as they or their forms may be sieving primes themselves
+                let survives = candidate < SIEVE_BOUND
+                    || residues
+                        .iter()
+                        .zip(&excluded)
+                        .all(|((r, _), (_, bad))| !bad.contains(r));
+                if survives {
+                    if let Some(x) = accept(&candidate, rng) {
+                        return Some(x);
+                    }
+                }
+                if candidate >= end {
+                    break;
+                }
+                candidate += &self.modulus;
+                for ((r, step), (p, _)) in residues.iter_mut().zip(&excluded) {
+                    *r = (*r + *step) % *p;
+                }
+            }
+        }
+        None
+    }
+
+    /// Whether the sieved `candidate` is a prime, with certainty below 2^64 and up to an error of 4^-64 above
+    pub(super) fn is_probable_prime(
+        candidate: &Integer,
+        rng: &mut (impl RandomSource + ?Sized),
+    ) -> bool {
+        if candidate.clone().miller_rabin(Integer::from(2)) == MillerRabinCompositeResult::Composite
+        {
+            return false;
+        }
+        if let Some(small) = candidate.to_u64() {
+            return small.is_prime();
+        }
+        let bases = Integer::from(candidate - 3);
+        (0..MILLER_RABIN_ROUNDS).all(|_| {
+            let base = rng.random_below(&bases) + 2;
+            candidate.clone().miller_rabin(base) == MillerRabinCompositeResult::MaybePrime
+        })
+    }
+
+    /// Check that the largest allowed number fits into `T`
+    fn assert_fits<T: TryFrom<Integer>>(&self) {
+        assert!(
+            T::try_from(self.interval().1).is_ok(),
+            "Bit length exceeds the prime type"
+        );
+    }
+
+    /// Random prime satisfying all constraints
+    ///
+    /// # Returns
+    /// `None` if no prime satisfies the constraints
+    ///
+    /// # Panics
+    /// If the bit length exceeds `T`
+    pub fn generate<T: TryFrom<Integer>>(
+        &self,
+        rng: &mut (impl RandomSource + ?Sized),
+    ) -> Option<T> {
+        self.assert_fits::<T>();
+        self.search(rng, &[], |candidate, rng| {
+            Self::is_probable_prime(candidate, rng)
+                .then(|| T::try_from(candidate.clone()).unwrap_or_else(|_| unreachable!()))
+        })
+    }
+
+    /// Random prime satisfying all constraints, with its certificate, see [`Primality::generate_lucas_certificate`]
+    ///
+    /// Certification factors p - 1, which quickly becomes impractical for large primes.
+    ///
+    /// # Returns
+    /// `None` if no prime satisfies the constraints
+    ///
+    /// # Panics
+    /// If the bit length exceeds `T`
+    pub fn generate_certified<T: TryFrom<Integer> + Primality + Clone>(
+        &self,
+        rng: &mut (impl RandomSource + ?Sized),
+    ) -> Option<(T, LucasCertificate<T>)> {
+        self.assert_fits::<T>();
+        self.search(rng, &[], |candidate, rng| {
+            if !Self::is_probable_prime(candidate, rng) {
+                return None;
+            }
+            let p = T::try_from(candidate.clone()).unwrap_or_else(|_| unreachable!());
+            let certificate = p.clone().generate_lucas_certificate()?;
+            Some((p, certificate))
+        })
+    }
+}
+
+/// Random prime with exactly `bits` bits, see [`PrimeGenerator`] for further constraints
+///
+/// # Panics
+/// If `bits` is below 2 or exceeds `T`
+///
+/// # Example
+/// ```
+/// use facto::generation::random_prime;
+/// use facto::Primality;
+/// use rug::rand::RandState;
+/// let mut rng = RandState::new();
+/// let p: u128 = random_prime(100, &mut rng);
+/// assert_eq!(128 - p.leading_zeros(), 100);
+/// assert!(p.is_prime());
+/// ```
+pub fn random_prime<T: TryFrom<Integer>>(bits: u32, rng: &mut (impl RandomSource + ?Sized)) -> T {
+    PrimeGenerator::new(bits)
+        .generate(rng)
+        .unwrap_or_else(|| unreachable!())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{random_prime, PrimeGenerator, RandomSource, RngCoreSource, TopBits};
+    use crate::{Integer, LucasCertificateElement, Primality};
+    use rand_core::{impls, Error, RngCore};
+    use rug::rand::RandState;
+    use std::convert::TryFrom;
+
+    /// Linear congruential generator
+    struct Lcg(u64);
+
+    impl RngCore for Lcg {
+        fn next_u32(&mut self) -> u32 {
+            u32::try_from(self.next_u64() >> 32).unwrap()
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            self.0 = self
+                .0
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            self.0
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            impls::fill_bytes_via_next(self, dest);
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn bit_lengths() {
+        let mut rng = RandState::new();
+        for bits in 2..=64 {
+            for _ in 0..20 {
+                let p: u64 = random_prime(bits, &mut rng);
+                assert!(p.is_prime(), "{}", p);
+                assert_eq!(64 - p.leading_zeros(), bits);
+                let p: u64 = PrimeGenerator::new(bits)
+                    .top_bits(TopBits::Two)
+                    .generate(&mut rng)
+                    .unwrap();
+                assert!(p.is_prime(), "{}", p);
+                assert!(bits == 2 || p >> (bits - 2) == 3, "{}", p);
+                let p: u64 = PrimeGenerator::new(bits)
+                    .top_bits(TopBits::Free)
+                    .generate(&mut rng)
+                    .unwrap();
+                assert!(p.is_prime() && 64 - p.leading_zeros() <= bits, "{}", p);
+            }
+        }
+        let p: u128 = random_prime(128, &mut rng);
+        assert_eq!(p.leading_zeros(), 0);
+        assert!(p.is_prime());
+        let p: Integer = random_prime(1024, &mut rng);
+        assert_eq!(p.significant_bits(), 1024);
+        assert!(p.is_probably_prime(40) != rug::integer::IsPrime::No);
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds the prime type")]
+    fn too_many_bits() {
+        let _: u64 = random_prime(65, &mut RandState::new());
+    }
+
+    #[test]
+    fn all_primes_reached() {
+        let mut rng = RngCoreSource(Lcg(7));
+        let generator = PrimeGenerator::new(6).top_bits(TopBits::Free);
+        let mut found = vec![false; 64];
+        for _ in 0..2000 {
+            let p: u64 = generator.generate(&mut rng).unwrap();
+            found[usize::try_from(p).unwrap()] = true;
+        }
+        let expected: Vec<bool> = (0..64u64).map(Primality::is_prime).collect();
+        assert_eq!(found, expected);
+        let below = rng.random_below(&Integer::from(1000));
+        assert!(below < 1000);
+    }
+
+    #[test]
+    fn congruences() {
+        let mut rng = RandState::new();
+        let modulus = 1u64 << 20;
+        for bits in [24, 48, 64] {
+            let generator = PrimeGenerator::new(bits).congruent(12345, modulus);
+            for _ in 0..20 {
+                let p: u64 = generator.generate(&mut rng).unwrap();
+                assert!(p.is_prime());
+                assert_eq!(p % modulus, 12345);
+                assert_eq!(64 - p.leading_zeros(), bits);
+            }
+        }
+        let p: Integer = PrimeGenerator::new(512)
+            .congruent(-1, 65537)
+            .generate(&mut rng)
+            .unwrap();
+        assert_eq!(p.mod_u(65537), 65536);
+        // No candidates, or only composite ones
+        assert_eq!(
+            PrimeGenerator::new(4)
+                .congruent(1, 1000)
+                .generate::<u64>(&mut rng),
+            None
+        );
+        assert_eq!(
+            PrimeGenerator::new(4)
+                .congruent(1, 7)
+                .generate::<u64>(&mut rng),
+            None
+        );
+    }
+
+    #[test]
+    fn certified() {
+        let mut rng = RandState::new();
+        for bits in [2, 10, 40, 64] {
+            let (p, certificate) = PrimeGenerator::new(bits)
+                .generate_certified::<u64>(&mut rng)
+                .unwrap();
+            assert_eq!(certificate.get_max().unwrap().n, p);
+            assert!(certificate
+                .elements
+                .iter()
+                .all(LucasCertificateElement::verify));
+        }
+        let (p, certificate) = PrimeGenerator::new(100)
+            .congruent(1, 4)
+            .generate_certified::<Integer>(&mut rng)
+            .unwrap();
+        assert_eq!(p.mod_u(4), 1);
+        assert_eq!(certificate.get_max().unwrap().n, p);
+    }
+}
@@ -0,0 +1,423 @@
+use rug::integer::IsPrime;
+use rug::Integer;
+
+use super::provable::pocklington_element;
+use super::random::{PrimeGenerator, RandomSource};
+use crate::optimized_factoring::LucasCertificateTrait;
+use crate::{CertifiedFactorization, Factoring, LucasCertificate, Primality, PrimalityCertainty};
+
+/// Miller-Rabin rounds of the validators above 2^64, bounding the error probability by 4^-64
+const PROBABLE_PRIME_REPS: u32 = 64;
+
+/// Push the elements proving `n` prime to `certificate`, returning whether `n` is prime
+///
+/// If the prime `factor` of n - 1 exceeds the square root of n, n is proven prime by pocklington's criterion,
+/// else n - 1 is factored by [`CertifiedFactorization::certified_prime_check`].
+fn certify(
+    n: &Integer,
+    factor: Option<&Integer>,
+    certificate: &mut LucasCertificate<Integer>,
+) -> bool {
+    if let Some(factor) = factor.filter(|f| Integer::from(f.square_ref()) > *n) {
+        return pocklington_element(n, factor)
+            .map(|element| certificate.push(element))
+            .is_some();
+    }
+    n.clone()
+        .certified_prime_check(PrimalityCertainty::Certified(certificate))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Safe prime p = 2q + 1, q being a sophie germain prime
+///
+/// # Example
+/// ```
+/// use facto::generation::{is_safe_prime, SafePrime};
+/// use rug::rand::RandState;
+/// let mut rng = RandState::new();
+/// let prime = SafePrime::generate(256, &mut rng);
+/// assert_eq!(prime.p.significant_bits(), 256);
+/// assert_eq!(prime.q, prime.p.clone() >> 1);
+/// assert!(is_safe_prime(&prime.p));
+/// ```
+pub struct SafePrime {
+    /// The safe prime
+    pub p: Integer,
+    /// The sophie germain prime (p - 1) / 2
+    pub q: Integer,
+}
+
+impl SafePrime {
+    /// Random safe prime p with exactly `bits` bits
+    ///
+    /// The candidates q are sieved together with 2q + 1, before checking both like [`PrimeGenerator`].
+    ///
+    /// # Panics
+    /// If `bits` is below 3
+    pub fn generate(bits: u32, rng: &mut (impl RandomSource + ?Sized)) -> Self {
+        assert!(bits >= 3, "Safe primes have at least 3 bits");
+        PrimeGenerator::new(bits - 1)
+            .search(rng, &[(2, 1)], |q, rng| {
+                let p = Integer::from(q * 2u32) + 1u32;
+                (PrimeGenerator::is_probable_prime(q, rng)
+                    && PrimeGenerator::is_probable_prime(&p, rng))
+                .then(|| Self { p, q: q.clone() })
+            })
+            .unwrap_or_else(|| unreachable!())
+    }
+
+    /// Random safe prime whose sophie germain prime q has exactly `bits` bits
+    ///
+    /// # Panics
+    /// If `bits` is below 2
+    pub fn generate_sophie_germain(bits: u32, rng: &mut (impl RandomSource + ?Sized)) -> Self {
+        assert!(bits >= 2, "Primes have at least 2 bits");
+        Self::generate(bits + 1, rng)
+    }
+
+    #[must_use]
+    /// Certificate of p and q, q being certified by factoring q - 1, p by pocklington's criterion for q
+    ///
+    /// Factoring q - 1 quickly becomes impractical for large primes.
+    ///
+    /// # Returns
+    /// `None` if p or q is composite
+    pub fn certificate(&self) -> Option<LucasCertificate<Integer>> {
+        let mut certificate = LucasCertificate::default();
+        (certify(&self.q, None, &mut certificate)
+            && certify(&self.p, Some(&self.q), &mut certificate))
+        .then_some(certificate)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Strong prime p by Gordon's algorithm, with large prime factors r of p - 1, s of p + 1 and t of r - 1
+///
+/// # Example
+/// ```
+/// use facto::generation::StrongPrime;
+/// use facto::Integer;
+/// use rug::rand::RandState;
+/// let mut rng = RandState::new();
+/// let prime = StrongPrime::generate(512, &mut rng);
+/// assert_eq!(prime.p.significant_bits(), 512);
+/// assert!(Integer::from(&prime.p - 1).is_divisible(&prime.r));
+/// assert!(Integer::from(&prime.p + 1).is_divisible(&prime.s));
+/// assert!(Integer::from(&prime.r - 1).is_divisible(&prime.t));
+/// ```
+pub struct StrongPrime {
+    /// The strong prime
+    pub p: Integer,
+    /// Prime factor of p - 1
+    pub r: Integer,
+    /// Prime factor of p + 1
+    pub s: Integer,
+    /// Prime factor of r - 1
+    pub t: Integer,
+}
+
+impl StrongPrime {
+    /// Random strong prime p with exactly `bits` bits
+    ///
+    /// Random primes s and t are generated first, then r ≡ 1 modulo 2t, and finally p ≡ p₀ modulo 2rs,
+    /// with p₀ = 2 (s^(r - 2) mod r) s - 1 being 1 modulo r and -1 modulo s.
+    /// r and s have about half of the bits, t twenty bits less.
+    ///
+    /// # Panics
+    /// If `bits` is below 64
+    pub fn generate(bits: u32, rng: &mut (impl RandomSource + ?Sized)) -> Self {
+        assert!(bits >= 64, "Strong primes need at least 64 bits");
+        // Leaving 2^16 values to p ≡ p₀ modulo 2rs, and 2^19 to r ≡ 1 modulo 2t
+        let half = (bits - 18) / 2;
+        loop {
+            let s: Integer = PrimeGenerator::new(half)
+                .generate(rng)
+                .unwrap_or_else(|| unreachable!());
+            let t: Integer = PrimeGenerator::new(half - 20)
+                .generate(rng)
+                .unwrap_or_else(|| unreachable!());
+            let r: Option<Integer> = PrimeGenerator::new(half)
+                .congruent(1, Integer::from(&t * 2u32))
+                .generate(rng);
+            let Some(r) = r.filter(|r| *r != s) else {
+                continue;
+            };
+            let inverse = Integer::from(
+                s.pow_mod_ref(&Integer::from(&r - 2u32), &r)
+                    .unwrap_or_else(|| unreachable!()),
+            );
+            let p0 = Integer::from(&inverse * &s) * 2u32 - 1u32;
+            let modulus = Integer::from(&r * &s) * 2u32;
+            if let Some(p) = PrimeGenerator::new(bits)
+                .congruent(p0, modulus)
+                .generate(rng)
+            {
+                return Self { p, r, s, t };
+            }
+        }
+    }
+
+    #[must_use]
+    /// Certificate of p, r, s and t, r and p being certified by pocklington's criterion when possible, the others by factoring n - 1
+    ///
+    /// Factoring p - 1 quickly becomes impractical for large primes.
+    ///
+    /// # Returns
+    /// `None` if any of the primes is composite
+    pub fn certificate(&self) -> Option<LucasCertificate<Integer>> {
+        let mut certificate = LucasCertificate::default();
+        (certify(&self.t, None, &mut certificate)
+            && certify(&self.s, None, &mut certificate)
+            && certify(&self.r, Some(&self.t), &mut certificate)
+            && certify(&self.p, Some(&self.r), &mut certificate))
+        .then_some(certificate)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// DSA style domain parameters, a prime p with a prime factor q of p - 1
+///
+/// # Example
+/// ```
+/// use facto::generation::{is_dsa_pair, DsaPrimes};
+/// use rug::rand::RandState;
+/// let mut rng = RandState::new();
+/// let primes = DsaPrimes::generate(1024, 160, &mut rng);
+/// assert_eq!(primes.p.significant_bits(), 1024);
+/// assert_eq!(primes.q.significant_bits(), 160);
+/// assert!(is_dsa_pair(&primes.p, &primes.q));
+/// ```
+pub struct DsaPrimes {
+    /// The prime modulus
+    pub p: Integer,
+    /// The prime order of the subgroup, dividing p - 1
+    pub q: Integer,
+}
+
+impl DsaPrimes {
+    /// Random primes p with exactly `p_bits` bits and q with exactly `q_bits` bits, with q dividing p - 1
+    ///
+    /// # Panics
+    /// If `q_bits` is below 2, or `p_bits` does not exceed `q_bits` by at least 2
+    pub fn generate(p_bits: u32, q_bits: u32, rng: &mut (impl RandomSource + ?Sized)) -> Self {
+        assert!(
+            q_bits >= 2 && p_bits >= q_bits + 2,
+            "p needs at least 2 bits more than q"
+        );
+        loop {
+            let q: Integer = PrimeGenerator::new(q_bits)
+                .generate(rng)
+                .unwrap_or_else(|| unreachable!());
+            let p = PrimeGenerator::new(p_bits)
+                .congruent(1, Integer::from(&q * 2u32))
+                .generate(rng);
+            if let Some(p) = p {
+                return Self { p, q };
+            }
+        }
+    }
+
+    #[must_use]
+    /// Certificate of p and q, by pocklington's criterion for q if it exceeds the square root of p, else by factoring p - 1
+    ///
+    /// Factoring p - 1 quickly becomes impractical for large primes.
+    ///
+    /// # Returns
+    /// `None` if p or q is composite
+    pub fn certificate(&self) -> Option<LucasCertificate<Integer>> {
+        let mut certificate = LucasCertificate::default();
+        (certify(&self.q, None, &mut certificate)
+            && certify(&self.p, Some(&self.q), &mut certificate))
+        .then_some(certificate)
+    }
+}
+
+/// Whether `n` is prime, with certainty below 2^64 and up to an error of 4^-64 above
+///
+/// Proving the primality of large numbers would mean factoring n - 1, so the validators leave it to the certificates.
+fn is_probable_prime(n: &Integer) -> bool {
+    n.to_u64().map_or_else(
+        || n.is_probably_prime(PROBABLE_PRIME_REPS) != IsPrime::No,
+        Primality::is_prime,
+    )
+}
+
+#[must_use]
+/// Whether `p` is a safe prime, p and (p - 1) / 2 being prime
+///
+/// Both are checked using [`Integer::is_probably_prime`] above 2^64, with an error probability below 4^-64.
+///
+/// # Example
+/// ```
+/// use facto::generation::is_safe_prime;
+/// use facto::Integer;
+/// assert!(is_safe_prime(&Integer::from(23)));
+/// assert!(!is_safe_prime(&Integer::from(13)));
+/// ```
+pub fn is_safe_prime(p: &Integer) -> bool {
+    p.is_odd() && is_probable_prime(p) && is_probable_prime(&Integer::from(p >> 1))
+}
+
+#[must_use]
+/// Whether `q` is a sophie germain prime, q and 2q + 1 being prime
+///
+/// Both are checked like in [`is_safe_prime`].
+///
+/// # Example
+/// ```
+/// use facto::generation::is_sophie_germain_prime;
+/// use facto::Integer;
+/// assert!(is_sophie_germain_prime(&Integer::from(11)));
+/// assert!(!is_sophie_germain_prime(&Integer::from(13)));
+/// ```
+pub fn is_sophie_germain_prime(q: &Integer) -> bool {
+    is_probable_prime(q) && is_probable_prime(&(Integer::from(q * 2u32) + 1u32))
+}
+
+/// Largest prime factor of the positive `n`, or 1
+fn largest_prime_factor(n: Integer) -> Integer {
+    n.factor()
+        .into_iter()
+        .max()
+        .unwrap_or_else(|| Integer::from(1))
+}
+
+#[must_use]
+/// Whether `p` is a strong prime, p - 1 having a prime factor r, p + 1 a prime factor s and r - 1 a prime factor t,
+/// each with at least `bits` bits
+///
+/// p - 1, p + 1 and r - 1 for every large enough r are factored using [`Factoring::factor`],
+/// which quickly becomes impractical for large primes.
+///
+/// # Example
+/// ```
+/// use facto::generation::is_strong_prime;
+/// use facto::Integer;
+/// // p - 1 = 2^2 * 3 * 83_333_903, p + 1 = 2 * 500_003_419, 83_333_903 - 1 = 2 * 41_666_951
+/// assert!(is_strong_prime(&Integer::from(1_000_006_837), 26));
+/// assert!(!is_strong_prime(&Integer::from(1_000_006_837), 27));
+/// // p + 1 = 2^3 * 3^2 * 7 * 109^2 * 167
+/// assert!(!is_strong_prime(&Integer::from(1_000_000_007), 9));
+/// ```
+pub fn is_strong_prime(p: &Integer, bits: u32) -> bool {
+    if *p < 5 || !is_probable_prime(p) {
+        return false;
+    }
+    if largest_prime_factor(Integer::from(p + 1u32)).significant_bits() < bits {
+        return false;
+    }
+    let mut factors = Integer::from(p - 1u32).factor();
+    factors.sort_unstable();
+    factors.dedup();
+    factors
+        .into_iter()
+        .filter(|r| r.significant_bits() >= bits)
+        .any(|r| largest_prime_factor(r - 1u32).significant_bits() >= bits)
+}
+
+#[must_use]
+/// Whether `p` and `q` are prime, with q dividing p - 1
+///
+/// Both are checked like in [`is_safe_prime`].
+///
+/// # Example
+/// ```
+/// use facto::generation::is_dsa_pair;
+/// use facto::Integer;
+/// assert!(is_dsa_pair(&Integer::from(23), &Integer::from(11)));
+/// assert!(!is_dsa_pair(&Integer::from(29), &Integer::from(11)));
+/// ```
+pub fn is_dsa_pair(p: &Integer, q: &Integer) -> bool {
+    is_probable_prime(p) && is_probable_prime(q) && Integer::from(p - 1u32).is_divisible(q)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        is_dsa_pair, is_safe_prime, is_sophie_germain_prime, is_strong_prime, DsaPrimes, SafePrime,
+        StrongPrime,
+    };
+    use crate::{Integer, LucasCertificateElement};
+    use rug::rand::RandState;
+
+    #[test]
+    fn safe_primes() {
+        let mut rng = RandState::new();
+        for bits in 3..=80 {
+            let prime = SafePrime::generate(bits, &mut rng);
+            assert_eq!(prime.p.significant_bits(), bits);
+            assert_eq!(prime.p, Integer::from(&prime.q * 2u32) + 1u32);
+            assert!(is_safe_prime(&prime.p));
+            assert!(is_sophie_germain_prime(&prime.q));
+            let certificate = prime.certificate().unwrap();
+            assert!(certificate
+                .elements
+                .iter()
+                .all(LucasCertificateElement::verify));
+            assert!(certificate.element(&prime.p).is_some());
+        }
+        for bits in 2..=40 {
+            let prime = SafePrime::generate_sophie_germain(bits, &mut rng);
+            assert_eq!(prime.q.significant_bits(), bits);
+            assert!(is_sophie_germain_prime(&prime.q));
+        }
+    }
+
+    #[test]
+    fn validators() {
+        let safe: Vec<Integer> = [5, 7, 11, 23, 47, 59, 83, 107, 167, 179]
+            .iter()
+            .map(|p| Integer::from(*p))
+            .collect();
+        for p in 0..100 {
+            let p = Integer::from(p);
+            assert_eq!(is_safe_prime(&p), safe.contains(&p), "{p}");
+            assert_eq!(
+                is_sophie_germain_prime(&p),
+                safe.contains(&(Integer::from(&p * 2u32) + 1u32)),
+                "{p}"
+            );
+        }
+        assert!(is_dsa_pair(&Integer::from(101), &Integer::from(5)));
+        assert!(!is_dsa_pair(&Integer::from(101), &Integer::from(3)));
+        assert!(!is_dsa_pair(&Integer::from(121), &Integer::from(5)));
+        assert!(!is_strong_prime(&Integer::from(3), 1));
+        // p - 1 = 2 * 83 * 97, p + 1 = 2^3 * 3 * 11 * 61, only 83 - 1 = 2 * 41 has a large prime factor
+        assert!(is_strong_prime(&Integer::from(16_103), 6));
+        assert!(!is_strong_prime(&Integer::from(16_103), 7));
+    }
+
+    #[test]
+    fn strong_primes() {
+        let mut rng = RandState::new();
+        let prime = StrongPrime::generate(96, &mut rng);
+        assert_eq!(prime.p.significant_bits(), 96);
+        assert!(Integer::from(&prime.p - 1u32).is_divisible(&prime.r));
+        assert!(Integer::from(&prime.p + 1u32).is_divisible(&prime.s));
+        assert!(Integer::from(&prime.r - 1u32).is_divisible(&prime.t));
+        assert!(is_strong_prime(&prime.p, 19));
+        let certificate = prime.certificate().unwrap();
+        assert!(certificate
+            .elements
+            .iter()
+            .all(LucasCertificateElement::verify));
+        assert!(certificate.element(&prime.p).is_some());
+    }
+
+    #[test]
+    fn dsa_primes() {
+        let mut rng = RandState::new();
+        for (p_bits, q_bits) in [(96, 40), (100, 60)] {
+            let primes = DsaPrimes::generate(p_bits, q_bits, &mut rng);
+            assert_eq!(primes.p.significant_bits(), p_bits);
+            assert_eq!(primes.q.significant_bits(), q_bits);
+            assert!(is_dsa_pair(&primes.p, &primes.q));
+            let certificate = primes.certificate().unwrap();
+            assert!(certificate
+                .elements
+                .iter()
+                .all(LucasCertificateElement::verify));
+            assert!(certificate.dangling().is_empty());
+        }
+    }
+}
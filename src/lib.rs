@@ -16,20 +16,36 @@
 #![warn(clippy::pedantic, clippy::nursery, clippy::cargo)]
 #![allow(clippy::unseparated_literal_suffix)]
 
+#[macro_use]
+mod trace;
+
+/// Arithmetic functions like Euler's totient, computed from the factorization
+pub mod arithmetic;
 /// Factorization algorithms for integers
 pub mod factoring;
+/// Generation of random primes
+pub mod generation;
 mod optimized_factoring;
 /// Primality checking algorithms for integers
 pub mod primality;
+/// Prime sieves enumerating the primes of a range
+pub mod sieve;
 mod util;
 /// Montgomery multiplication methods
 pub use redc;
 
 pub use optimized_factoring::{
-    CertifiedFactorization, EmptyFactoringEventSubscriptor, Factoring, FactoringEventSubscriptor,
-    LucasCertificate, LucasCertificateElement, Primality, PrimalityCertainty,
+    CancellationToken, CertificateElementKind, CertificationEventSubscriptor,
+    CertifiedFactorization, EmptyCertificationEventSubscriptor, EmptyFactoringEventSubscriptor,
+    FactorStatus, FactorTreeNode, FactorTreeRecorder, Factoring, FactoringAlgorithm,
+    FactoringBudget, FactoringEventSubscriptor, FactoringSession, Factorization, LucasCertificate,
+    LucasCertificateElement, ParseFactorizationError, PartialFactorization, Primality,
+    PrimalityCertainty,
 };
 
+#[cfg(feature = "tracing")]
+pub use optimized_factoring::TracingSubscriptor;
+
 #[doc(no_inline)]
 pub use rug::Integer;
 
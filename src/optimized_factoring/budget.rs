@@ -0,0 +1,132 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::factoring::IterationLimit;
+
+#[derive(Debug, Default, Clone)]
+/// Flag to abort a running factorization from another thread
+///
+/// Clones share the flag, cancelling one cancels all of them
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    #[must_use]
+    /// Create a token which has not been cancelled yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Abort all factorizations using this token
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    #[must_use]
+    /// Check if [`CancellationToken::cancel`] has been called on this token or any of its clones
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+/// Limits on the effort spent by [`crate::Factoring::factor_with_budget`]
+///
+/// The default budget is unlimited
+pub struct FactoringBudget {
+    /// Point in time after which factoring is aborted
+    pub deadline: Option<Instant>,
+    /// Maximum number of pollard rho iterations, summed up over all attempts
+    pub max_iterations: Option<u64>,
+    /// Token aborting the factorization once it is cancelled
+    pub cancellation: Option<CancellationToken>,
+}
+
+impl FactoringBudget {
+    pub(crate) const fn tracker(&self) -> BudgetTracker<'_> {
+        BudgetTracker {
+            budget: self,
+            iterations: 0,
+            exhausted: false,
+        }
+    }
+}
+
+/// Tracks the iterations spent against a [`FactoringBudget`]
+pub struct BudgetTracker<'a> {
+    budget: &'a FactoringBudget,
+    iterations: u64,
+    exhausted: bool,
+}
+
+impl BudgetTracker<'_> {
+    /// Reading the clock on every iteration would slow down the small numbers considerably
+    const CLOCK_INTERVAL: u64 = 1 << 10;
+}
+
+impl IterationLimit for BudgetTracker<'_> {
+    fn step(&mut self) -> bool {
+        if self.exhausted {
+            return true;
+        }
+        self.iterations += 1;
+        self.exhausted = self
+            .budget
+            .max_iterations
+            .is_some_and(|max| self.iterations > max)
+            || self
+                .budget
+                .cancellation
+                .as_ref()
+                .is_some_and(CancellationToken::is_cancelled)
+            || (self.iterations % Self::CLOCK_INTERVAL == 1
+                && self
+                    .budget
+                    .deadline
+                    .is_some_and(|deadline| Instant::now() >= deadline));
+        self.exhausted
+    }
+
+    fn exhausted(&self) -> bool {
+        self.exhausted
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Result of a factorization which may have been aborted
+///
+/// The product of all parts equals the factored number
+pub struct PartialFactorization<T> {
+    /// Factors proven to be prime, in ascending order
+    pub primes: Vec<T>,
+    /// Factors proven to be composite, which could not be split further
+    pub composites: Vec<T>,
+    /// Factors of which the primality is unknown
+    pub unknown: Vec<T>,
+}
+
+impl<T> Default for PartialFactorization<T> {
+    fn default() -> Self {
+        Self {
+            primes: vec![],
+            composites: vec![],
+            unknown: vec![],
+        }
+    }
+}
+
+impl<T> PartialFactorization<T> {
+    #[must_use]
+    /// Check if the factorization finished, i.e. `primes` is the complete prime factorization
+    pub const fn is_complete(&self) -> bool {
+        self.composites.is_empty() && self.unknown.is_empty()
+    }
+
+    pub(crate) fn convert<F: From<T>>(self) -> PartialFactorization<F> {
+        PartialFactorization {
+            primes: self.primes.into_iter().map(F::from).collect(),
+            composites: self.composites.into_iter().map(F::from).collect(),
+            unknown: self.unknown.into_iter().map(F::from).collect(),
+        }
+    }
+}
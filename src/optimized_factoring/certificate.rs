@@ -1,5 +1,26 @@
 use std::{convert::TryFrom, marker::PhantomData};
 
+use crate::primality::{
+    LucasPrimality, LucasPrimalityResult, MillerRabin, MillerRabinCompositeResult,
+};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+/// The kind of proof a [`LucasCertificateElement`] carries
+pub enum CertificateElementKind {
+    /// `n` passes the lucas primality test for `base`, `unique_prime_divisors` being the unique prime divisors of `n` - 1
+    #[default]
+    Lucas,
+    /// `n` is below 2^64 and passes the miller rabin test for the bases 2, 325, 9375, 28178, 450775, 9780504 and 1795265022,
+    /// which is deterministic for this range.
+    ///
+    /// `base` and `unique_prime_divisors` carry no meaning
+    MillerRabin,
+    /// `n` passes pocklington's criterion for `base`, `unique_prime_divisors` being prime divisors of `n` - 1
+    /// whose full powers dividing `n` - 1 multiply to more than the square root of `n`
+    Pocklington,
+}
+
 #[derive(Debug, Default, Clone, PartialEq)]
 /// Element of the lucas certificate tree, representing one number
 pub struct LucasCertificateElement<T> {
@@ -9,6 +30,59 @@ pub struct LucasCertificateElement<T> {
     pub base: T,
     /// The unique divisors of `n` - 1
     pub unique_prime_divisors: Vec<T>,
+    /// How the primality of `n` is proven
+    pub kind: CertificateElementKind,
+}
+
+impl<T> LucasCertificateElement<T>
+where
+    T: Clone + PartialEq + From<u8> + MillerRabin + LucasPrimality,
+    u64: TryFrom<T>,
+{
+    /// Check the proof of this single element
+    ///
+    /// For [`CertificateElementKind::Lucas`] elements only the lucas primality test itself is run.
+    /// That `unique_prime_divisors` are exactly the prime divisors of `n` - 1, and that those are certified as well, is left to the caller.
+    /// The same holds for [`CertificateElementKind::Pocklington`] elements, whose divisors only need to be primes.
+    ///
+    /// # Example
+    /// ```
+    /// use facto::{CertificateElementKind, LucasCertificateElement};
+    /// let e = LucasCertificateElement {
+    ///     n: 1_000_000_007u64,
+    ///     base: 0,
+    ///     unique_prime_divisors: vec![],
+    ///     kind: CertificateElementKind::MillerRabin,
+    /// };
+    /// assert!(e.verify());
+    /// ```
+    #[must_use]
+    pub fn verify(&self) -> bool {
+        match self.kind {
+            CertificateElementKind::Lucas => {
+                if self.n == T::from(2) {
+                    return true;
+                }
+                self.n.clone().miller_rabin(T::from(2)) == MillerRabinCompositeResult::MaybePrime
+                    && self
+                        .n
+                        .clone()
+                        .lucas_primality_test(&self.unique_prime_divisors, self.base.clone())
+                        == LucasPrimalityResult::Prime
+            }
+            CertificateElementKind::Pocklington => {
+                self.n
+                    .clone()
+                    .pocklington_test(&self.unique_prime_divisors, self.base.clone())
+                    == LucasPrimalityResult::Prime
+            }
+            CertificateElementKind::MillerRabin => u64::try_from(self.n.clone()).is_ok_and(|n| {
+                super::DETERMINISTIC_U64_BASES
+                    .iter()
+                    .all(|base| n.miller_rabin(*base) == MillerRabinCompositeResult::MaybePrime)
+            }),
+        }
+    }
 }
 
 #[allow(clippy::module_name_repetitions)]
@@ -36,7 +110,9 @@ pub struct WrappingLucasCertificate<'a, F, T> {
     _phantom_from: PhantomData<F>,
 }
 
-fn change_element<F, T: From<F>>(c: LucasCertificateElement<F>) -> LucasCertificateElement<T> {
+pub(super) fn change_element<F, T: From<F>>(
+    c: LucasCertificateElement<F>,
+) -> LucasCertificateElement<T> {
     LucasCertificateElement {
         n: c.n.into(),
         base: c.base.into(),
@@ -45,6 +121,7 @@ fn change_element<F, T: From<F>>(c: LucasCertificateElement<F>) -> LucasCertific
             .into_iter()
             .map(std::convert::Into::into)
             .collect(),
+        kind: c.kind,
     }
 }
 
@@ -91,6 +168,139 @@ where
     }
 }
 
+impl<T> LucasCertificateElement<T>
+where
+    T: PartialEq + From<u8>,
+{
+    /// Numbers whose primality this element depends on
+    ///
+    /// Empty for the element of 2, which lists 1 as the divisor of 1, and for [`CertificateElementKind::MillerRabin`] elements
+    #[must_use]
+    pub fn references(&self) -> &[T] {
+        if self.kind == CertificateElementKind::MillerRabin || self.n == T::from(2) {
+            return &[];
+        }
+        &self.unique_prime_divisors
+    }
+}
+
+/// Navigation of the certificate as a directed acyclic graph.
+/// Every element points to the elements certifying the unique prime divisors of its `n` - 1.
+///
+/// Lookups rely on `elements` being sorted by `n`, as maintained by [`LucasCertificateTrait::push`]
+///
+/// # Example
+/// ```
+/// use facto::{CertifiedFactorization, LucasCertificate, PrimalityCertainty};
+/// let mut c = LucasCertificate::default();
+/// assert!(1009u64.certified_prime_check(PrimalityCertainty::Certified(&mut c)));
+/// // 1009 - 1 = 2^4 * 3^2 * 7, 7 - 1 = 2 * 3, 3 - 1 = 2
+/// let root = c.element(&1009).unwrap();
+/// assert_eq!(c.children(root).map(|x| x.n).collect::<Vec<_>>(), vec![2, 3, 7]);
+/// assert_eq!(c.roots().map(|x| x.n).collect::<Vec<_>>(), vec![1009]);
+/// assert_eq!(c.depth(&1009), Some(4));
+/// assert_eq!(c.size(&1009), Some(4));
+/// assert!(c.dangling().is_empty());
+/// ```
+impl<T> LucasCertificate<T>
+where
+    T: Ord + From<u8>,
+{
+    /// Get proof element for number `n` without cloning it
+    #[must_use]
+    pub fn element(&self, n: &T) -> Option<&LucasCertificateElement<T>> {
+        self.elements
+            .binary_search_by(|x| x.n.cmp(n))
+            .ok()
+            .map(|i| &self.elements[i])
+    }
+
+    /// Elements certifying the prime divisors `e` depends on, skipping divisors missing from the certificate
+    pub fn children<'a>(
+        &'a self,
+        e: &'a LucasCertificateElement<T>,
+    ) -> impl Iterator<Item = &'a LucasCertificateElement<T>> + 'a {
+        e.references().iter().filter_map(move |x| self.element(x))
+    }
+
+    /// Elements depending on the primality of `prime`
+    pub fn parents<'a>(
+        &'a self,
+        prime: &'a T,
+    ) -> impl Iterator<Item = &'a LucasCertificateElement<T>> + 'a {
+        self.elements
+            .iter()
+            .filter(move |x| x.references().contains(prime))
+    }
+
+    /// Elements no other element depends on, usually the numbers which were meant to be certified
+    pub fn roots(&self) -> impl Iterator<Item = &LucasCertificateElement<T>> + '_ {
+        let referenced: std::collections::BTreeSet<&T> = self
+            .elements
+            .iter()
+            .flat_map(LucasCertificateElement::references)
+            .collect();
+        self.elements
+            .iter()
+            .filter(move |x| !referenced.contains(&x.n))
+    }
+
+    /// Referenced numbers which have no element in the certificate, rendering it incomplete
+    #[must_use]
+    pub fn dangling(&self) -> Vec<&T> {
+        let mut result: Vec<&T> = self
+            .elements
+            .iter()
+            .flat_map(LucasCertificateElement::references)
+            .filter(|x| self.element(x).is_none())
+            .collect();
+        result.sort_unstable();
+        result.dedup();
+        result
+    }
+
+    /// Length of the longest chain of elements starting at `n`, counting `n` itself
+    ///
+    /// Returns `None` if `n` is not part of the certificate, or a malformed certificate has a cycle reachable from `n`
+    #[must_use]
+    pub fn depth(&self, n: &T) -> Option<usize> {
+        fn depth_rec<'a, T: Ord + From<u8>>(
+            c: &'a LucasCertificate<T>,
+            e: &'a LucasCertificateElement<T>,
+            known: &mut std::collections::BTreeMap<&'a T, Option<usize>>,
+        ) -> Option<usize> {
+            if let Some(d) = known.get(&e.n) {
+                // Elements still being visited are marked by `None`, reaching them again means a cycle
+                return *d;
+            }
+            known.insert(&e.n, None);
+            let mut d = 0;
+            for x in c.children(e) {
+                d = d.max(depth_rec(c, x, known)?);
+            }
+            known.insert(&e.n, Some(d + 1));
+            Some(d + 1)
+        }
+        let e = self.element(n)?;
+        depth_rec(self, e, &mut std::collections::BTreeMap::new())
+    }
+
+    /// Number of distinct elements reachable from `n`, counting `n` itself
+    ///
+    /// Returns `None` if `n` is not part of the certificate
+    #[must_use]
+    pub fn size(&self, n: &T) -> Option<usize> {
+        let mut visited = std::collections::BTreeSet::new();
+        let mut stack = vec![self.element(n)?];
+        while let Some(e) = stack.pop() {
+            if visited.insert(&e.n) {
+                stack.extend(self.children(e));
+            }
+        }
+        Some(visited.len())
+    }
+}
+
 impl<T: Eq + Ord + Clone> LucasCertificateTrait<T> for LucasCertificate<T>
 where
     T: std::fmt::Debug,
@@ -114,3 +324,52 @@ impl<T> std::convert::From<LucasCertificateElement<T>> for LucasCertificate<T> {
         Self { elements: vec![x] }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{CertificateElementKind, LucasCertificate, LucasCertificateElement};
+
+    fn element(n: u64, base: u64, unique_prime_divisors: Vec<u64>) -> LucasCertificateElement<u64> {
+        LucasCertificateElement {
+            n,
+            base,
+            unique_prime_divisors,
+            kind: CertificateElementKind::Lucas,
+        }
+    }
+
+    #[test]
+    fn test_navigation() {
+        let c = LucasCertificate {
+            elements: vec![
+                element(2, 1, vec![1]),
+                element(3, 2, vec![2]),
+                element(7, 3, vec![2, 3]),
+                element(13, 2, vec![2, 3]),
+                element(29, 2, vec![2, 7]),
+                element(43, 3, vec![2, 3, 7]),
+                element(59, 2, vec![2, 29]),
+            ],
+        };
+        assert_eq!(c.roots().map(|x| x.n).collect::<Vec<_>>(), vec![13, 43, 59]);
+        assert_eq!(c.parents(&7).map(|x| x.n).collect::<Vec<_>>(), vec![29, 43]);
+        assert_eq!(c.parents(&2).count(), 6);
+        assert_eq!(c.depth(&2), Some(1));
+        assert_eq!(c.depth(&59), Some(5));
+        assert_eq!(c.depth(&5), None);
+        assert_eq!(c.size(&59), Some(5));
+        assert_eq!(c.size(&43), Some(4));
+        assert!(c.dangling().is_empty());
+
+        let mut broken = c;
+        broken.elements.remove(2);
+        assert_eq!(broken.dangling(), vec![&7]);
+        assert_eq!(broken.depth(&59), Some(3));
+
+        let cyclic = LucasCertificate {
+            elements: vec![element(5, 2, vec![7]), element(7, 3, vec![5])],
+        };
+        assert_eq!(cyclic.depth(&5), None);
+        assert_eq!(cyclic.size(&5), Some(2));
+    }
+}
@@ -1,6 +1,25 @@
 use std::marker::PhantomData;
+use std::time::Duration;
+
+use super::certificate::change_element;
+use super::LucasCertificateElement;
+use crate::factoring::PollardRhoStatistics;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// Algorithm applied to a number during its factorization
+pub enum FactoringAlgorithm {
+    /// Trial division by small numbers, see [`crate::factoring::TrialDivision`]
+    TrialDivision,
+    /// Repeated attempts of pollard's rho until a factor is found, see [`crate::factoring::PollardRho`]
+    PollardRho,
+    /// Checking, and possibly certifying, the primality of a factor
+    PrimalityCheck,
+}
 
 /// Observer with callbacks relating to events during the factorization of an integer
+///
+/// Only [`FactoringEventSubscriptor::factorized`], [`FactoringEventSubscriptor::is_prime`] and [`FactoringEventSubscriptor::is_composite`] need to be implemented,
+/// the callbacks reporting on the applied algorithms do nothing by default
 pub trait FactoringEventSubscriptor<T> {
     /// Number `n` has been factorized into parts
     /// # Arguments
@@ -13,6 +32,24 @@ pub trait FactoringEventSubscriptor<T> {
     fn is_prime(&mut self, n: &T);
     /// Factor `n` now known to be composite
     fn is_composite(&mut self, n: &T);
+
+    /// `algorithm` is being applied to `n`
+    fn algorithm_started(&mut self, _n: &T, _algorithm: FactoringAlgorithm) {}
+    /// `algorithm` has been applied to `n`, taking `elapsed` time
+    fn algorithm_finished(&mut self, _n: &T, _algorithm: FactoringAlgorithm, _elapsed: Duration) {}
+    /// Pollard's rho is run on `n`, generating the series `x` = `x` * `x` + `increment` starting at `seed`
+    fn pollard_rho_attempt(&mut self, _n: &T, _seed: &T, _increment: &T) {}
+    /// Pollard's rho run on `n` using `increment` finished, finding `factor` if it was successful
+    fn pollard_rho_result(
+        &mut self,
+        _n: &T,
+        _increment: &T,
+        _statistics: &PollardRhoStatistics,
+        _factor: Option<&T>,
+    ) {
+    }
+    /// Pollard's rho failed on `n` for the `retries`th time, and will be run again using `next_increment`
+    fn pollard_rho_retry(&mut self, _n: &T, _next_increment: &T, _retries: u64) {}
 }
 
 /// Stub observer, for when no event callbacks are required
@@ -25,19 +62,112 @@ impl<T> FactoringEventSubscriptor<T> for EmptyFactoringEventSubscriptor {
     fn is_composite(&mut self, _n: &T) {}
 }
 
-pub struct WrappingFactoringEventSubscriptor<Inner, F, To>
+/// Observer with callbacks relating to events during the certification of a prime
+///
+/// All callbacks do nothing by default
+///
+/// # Example
+/// ```
+/// use facto::{CertificationEventSubscriptor, CertifiedFactorization, PrimalityCertainty};
+/// #[derive(Default)]
+/// struct Progress(Vec<u64>);
+/// impl CertificationEventSubscriptor<u64> for Progress {
+///     fn n_minus_one_factoring_started(&mut self, n: &u64) {
+///         self.0.push(*n);
+///     }
+/// }
+/// let mut c = facto::LucasCertificate::default();
+/// let mut progress = Progress::default();
+/// assert!(1009u64.certified_prime_check_events(PrimalityCertainty::Certified(&mut c), &mut progress));
+/// assert_eq!(progress.0, vec![1009, 3, 7]);
+/// ```
+pub trait CertificationEventSubscriptor<T> {
+    /// Certification of the primality of `n` started
+    fn certification_started(&mut self, _n: &T) {}
+    /// Certification of `n` finished after `elapsed` time, `prime` being its result
+    fn certification_finished(&mut self, _n: &T, _prime: bool, _elapsed: Duration) {}
+    /// `n` - 1 is being factored, to find the prime divisors required by the lucas primality test of `n`
+    fn n_minus_one_factoring_started(&mut self, _n: &T) {}
+    /// `n` - 1 has been factored, taking `elapsed` time
+    fn n_minus_one_factored(&mut self, _n: &T, _unique_prime_divisors: &[T], _elapsed: Duration) {}
+    /// `base` could not witness the primality of `n` in the lucas primality test
+    fn witness_failed(&mut self, _n: &T, _base: &T) {}
+    /// `element` has been added to the certificate
+    fn element_added(&mut self, _element: &LucasCertificateElement<T>) {}
+}
+
+/// Stub observer, for when no certification event callbacks are required
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct EmptyCertificationEventSubscriptor {}
+
+impl<T> CertificationEventSubscriptor<T> for EmptyCertificationEventSubscriptor {}
+
+pub struct WrappingCertificationEventSubscriptor<'a, F, To> {
+    inner: &'a mut dyn CertificationEventSubscriptor<To>,
+    _phantom_from: PhantomData<F>,
+}
+
+impl<'a, F, To> WrappingCertificationEventSubscriptor<'a, F, To> {
+    pub fn new(inner: &'a mut dyn CertificationEventSubscriptor<To>) -> Self {
+        Self {
+            inner,
+            _phantom_from: PhantomData,
+        }
+    }
+}
+
+impl<F, To> CertificationEventSubscriptor<F> for WrappingCertificationEventSubscriptor<'_, F, To>
+where
+    To: From<F>,
+    F: Clone,
+{
+    fn certification_started(&mut self, n: &F) {
+        self.inner.certification_started(&To::from(n.clone()));
+    }
+
+    fn certification_finished(&mut self, n: &F, prime: bool, elapsed: Duration) {
+        self.inner
+            .certification_finished(&To::from(n.clone()), prime, elapsed);
+    }
+
+    fn n_minus_one_factoring_started(&mut self, n: &F) {
+        self.inner
+            .n_minus_one_factoring_started(&To::from(n.clone()));
+    }
+
+    fn n_minus_one_factored(&mut self, n: &F, unique_prime_divisors: &[F], elapsed: Duration) {
+        let d_wrapped: Vec<To> = unique_prime_divisors
+            .iter()
+            .map(|x| To::from(x.clone()))
+            .collect();
+        self.inner
+            .n_minus_one_factored(&To::from(n.clone()), &d_wrapped, elapsed);
+    }
+
+    fn witness_failed(&mut self, n: &F, base: &F) {
+        self.inner
+            .witness_failed(&To::from(n.clone()), &To::from(base.clone()));
+    }
+
+    fn element_added(&mut self, element: &LucasCertificateElement<F>) {
+        self.inner.element_added(&change_element(element.clone()));
+    }
+}
+
+pub struct WrappingFactoringEventSubscriptor<'a, Inner, F, To>
 where
-    Inner: FactoringEventSubscriptor<To>,
+    Inner: FactoringEventSubscriptor<To> + ?Sized,
     To: From<F>,
 {
-    inner: Inner,
+    inner: &'a mut Inner,
     _phantom_from: PhantomData<F>,
     _phantom_to: PhantomData<To>,
 }
 
-impl<Inner, F, To> FactoringEventSubscriptor<F> for WrappingFactoringEventSubscriptor<Inner, F, To>
+impl<Inner, F, To> FactoringEventSubscriptor<F>
+    for WrappingFactoringEventSubscriptor<'_, Inner, F, To>
 where
-    Inner: FactoringEventSubscriptor<To>,
+    Inner: FactoringEventSubscriptor<To> + ?Sized,
     To: From<F>,
     F: Clone,
 {
@@ -56,15 +186,56 @@ where
     fn is_composite(&mut self, n: &F) {
         self.inner.is_composite(&To::from(n.clone()));
     }
+
+    fn algorithm_started(&mut self, n: &F, algorithm: FactoringAlgorithm) {
+        self.inner
+            .algorithm_started(&To::from(n.clone()), algorithm);
+    }
+
+    fn algorithm_finished(&mut self, n: &F, algorithm: FactoringAlgorithm, elapsed: Duration) {
+        self.inner
+            .algorithm_finished(&To::from(n.clone()), algorithm, elapsed);
+    }
+
+    fn pollard_rho_attempt(&mut self, n: &F, seed: &F, increment: &F) {
+        self.inner.pollard_rho_attempt(
+            &To::from(n.clone()),
+            &To::from(seed.clone()),
+            &To::from(increment.clone()),
+        );
+    }
+
+    fn pollard_rho_result(
+        &mut self,
+        n: &F,
+        increment: &F,
+        statistics: &PollardRhoStatistics,
+        factor: Option<&F>,
+    ) {
+        self.inner.pollard_rho_result(
+            &To::from(n.clone()),
+            &To::from(increment.clone()),
+            statistics,
+            factor.cloned().map(To::from).as_ref(),
+        );
+    }
+
+    fn pollard_rho_retry(&mut self, n: &F, next_increment: &F, retries: u64) {
+        self.inner.pollard_rho_retry(
+            &To::from(n.clone()),
+            &To::from(next_increment.clone()),
+            retries,
+        );
+    }
 }
 
-impl<Inner, F, To> WrappingFactoringEventSubscriptor<Inner, F, To>
+impl<'a, Inner, F, To> WrappingFactoringEventSubscriptor<'a, Inner, F, To>
 where
-    Inner: FactoringEventSubscriptor<To>,
+    Inner: FactoringEventSubscriptor<To> + ?Sized,
     To: From<F>,
     F: Clone,
 {
-    pub fn new(inner: Inner) -> Self {
+    pub fn new(inner: &'a mut Inner) -> Self {
         Self {
             inner,
             _phantom_to: std::marker::PhantomData::default(),
@@ -72,3 +243,79 @@ where
         }
     }
 }
+
+#[cfg(feature = "tracing")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+/// Observer forwarding all callbacks as debug events to [`tracing`]
+///
+/// Only available with the `tracing` feature
+///
+/// # Example
+/// ```
+/// use facto::{Factoring, TracingSubscriptor};
+/// assert_eq!(60u64.factor_events(&mut TracingSubscriptor), vec![2, 2, 3, 5]);
+/// ```
+pub struct TracingSubscriptor;
+
+#[cfg(feature = "tracing")]
+fn display_all<T: std::fmt::Display>(values: &[T]) -> String {
+    values
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(feature = "tracing")]
+impl<T: std::fmt::Display> FactoringEventSubscriptor<T> for TracingSubscriptor {
+    fn factorized(&mut self, n: &T, primes: &[T], composites: &[T], unknown: &[T]) {
+        tracing::debug!(
+            %n,
+            primes = %display_all(primes),
+            composites = %display_all(composites),
+            unknown = %display_all(unknown),
+            "factorized"
+        );
+    }
+
+    fn is_prime(&mut self, n: &T) {
+        tracing::debug!(%n, "is prime");
+    }
+
+    fn is_composite(&mut self, n: &T) {
+        tracing::debug!(%n, "is composite");
+    }
+
+    fn algorithm_started(&mut self, n: &T, algorithm: FactoringAlgorithm) {
+        tracing::debug!(%n, ?algorithm, "algorithm started");
+    }
+
+    fn algorithm_finished(&mut self, n: &T, algorithm: FactoringAlgorithm, elapsed: Duration) {
+        tracing::debug!(%n, ?algorithm, ?elapsed, "algorithm finished");
+    }
+
+    fn pollard_rho_attempt(&mut self, n: &T, seed: &T, increment: &T) {
+        tracing::debug!(%n, %seed, %increment, "pollard rho attempt");
+    }
+
+    fn pollard_rho_result(
+        &mut self,
+        n: &T,
+        increment: &T,
+        statistics: &PollardRhoStatistics,
+        factor: Option<&T>,
+    ) {
+        tracing::debug!(
+            %n,
+            %increment,
+            iterations = statistics.iterations,
+            gcds = statistics.gcds,
+            factor = factor.map(tracing::field::display),
+            "pollard rho result"
+        );
+    }
+
+    fn pollard_rho_retry(&mut self, n: &T, next_increment: &T, retries: u64) {
+        tracing::debug!(%n, %next_increment, retries, "pollard rho retry");
+    }
+}
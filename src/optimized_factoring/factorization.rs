@@ -0,0 +1,425 @@
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use std::ops::{Div, Mul};
+use std::str::FromStr;
+
+use crate::util::power;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// Prime factorization of a positive number, stored as pairs of a prime and its exponent
+///
+/// The primes are sorted ascending and unique, all exponents are positive.
+/// The empty factorization represents 1.
+///
+/// # Example
+/// ```
+/// use facto::{Factoring, Factorization};
+/// let f = 360u64.factor_exponents();
+/// assert_eq!(f.factors(), &[(2, 3), (3, 2), (5, 1)]);
+/// assert_eq!(f.to_string(), "2^3 * 3^2 * 5");
+/// assert_eq!(f.value(), 360);
+/// assert_eq!("2^3 * 3^2 * 5".parse::<Factorization<u64>>(), Ok(f));
+/// ```
+pub struct Factorization<T> {
+    factors: Vec<(T, u32)>,
+}
+
+impl<T> Default for Factorization<T> {
+    fn default() -> Self {
+        Self { factors: vec![] }
+    }
+}
+
+impl<T> Factorization<T> {
+    #[must_use]
+    /// The factorization of 1
+    pub fn one() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    /// Pairs of prime and exponent, sorted by prime
+    pub fn factors(&self) -> &[(T, u32)] {
+        &self.factors
+    }
+
+    /// The distinct primes, in ascending order
+    pub fn primes(&self) -> impl Iterator<Item = &T> {
+        self.factors.iter().map(|(p, _)| p)
+    }
+
+    #[must_use]
+    /// Check if this is the factorization of 1
+    pub const fn is_one(&self) -> bool {
+        self.factors.is_empty()
+    }
+
+    #[must_use]
+    /// Consume the factorization, returning the pairs of prime and exponent
+    pub fn into_factors(self) -> Vec<(T, u32)> {
+        self.factors
+    }
+}
+
+impl<T: Ord> Factorization<T> {
+    #[must_use]
+    /// Build the factorization from prime factors, which may be repeated and unordered
+    ///
+    /// The primality of the factors is not checked
+    pub fn from_primes(mut primes: Vec<T>) -> Self {
+        primes.sort_unstable();
+        let mut factors: Vec<(T, u32)> = vec![];
+        for p in primes {
+            match factors.last_mut() {
+                Some((last, e)) if *last == p => *e += 1,
+                _ => factors.push((p, 1)),
+            }
+        }
+        Self { factors }
+    }
+
+    #[must_use]
+    /// Build the factorization from pairs of prime and exponent, which may be repeated and unordered
+    ///
+    /// Pairs with an exponent of zero are dropped, the primality of the factors is not checked
+    ///
+    /// # Panics
+    /// If the exponents of a prime sum to more than `u32::MAX`, see [`Factorization::checked_from_factors`]
+    pub fn from_factors(factors: Vec<(T, u32)>) -> Self {
+        Self::checked_from_factors(factors).expect("Exponent overflow")
+    }
+
+    #[must_use]
+    /// Build the factorization from pairs of prime and exponent, or `None` if the exponents of a prime sum to more than `u32::MAX`
+    ///
+    /// Pairs with an exponent of zero are dropped, the primality of the factors is not checked
+    pub fn checked_from_factors(mut factors: Vec<(T, u32)>) -> Option<Self> {
+        factors.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        let mut result: Vec<(T, u32)> = vec![];
+        for (p, e) in factors {
+            match result.last_mut() {
+                Some((last, last_e)) if *last == p => *last_e = last_e.checked_add(e)?,
+                _ => result.push((p, e)),
+            }
+        }
+        result.retain(|(_, e)| *e > 0);
+        Some(Self { factors: result })
+    }
+
+    #[must_use]
+    /// Exponent of `prime`, zero if it does not divide the number
+    pub fn exponent(&self, prime: &T) -> u32 {
+        self.factors
+            .binary_search_by(|(p, _)| p.cmp(prime))
+            .map_or(0, |i| self.factors[i].1)
+    }
+
+    #[must_use]
+    /// Check if the number represented by `other` divides this one
+    pub fn is_divisible_by(&self, other: &Self) -> bool {
+        other.factors.iter().all(|(p, e)| self.exponent(p) >= *e)
+    }
+
+    /// Walk both factorizations in order of their primes, combining the exponents of each prime using `f`
+    fn merge(&self, other: &Self, f: impl Fn(u32, u32) -> u32) -> Self
+    where
+        T: Clone,
+    {
+        let mut factors = vec![];
+        let mut left = self.factors.iter().peekable();
+        let mut right = other.factors.iter().peekable();
+        loop {
+            let (p, e) = match (left.peek(), right.peek()) {
+                (None, None) => break,
+                (Some((p, e)), None) => {
+                    left.next();
+                    (p, f(*e, 0))
+                }
+                (None, Some((p, e))) => {
+                    right.next();
+                    (p, f(0, *e))
+                }
+                (Some((lp, le)), Some((rp, re))) => match lp.cmp(rp) {
+                    Ordering::Less => {
+                        left.next();
+                        (lp, f(*le, 0))
+                    }
+                    Ordering::Greater => {
+                        right.next();
+                        (rp, f(0, *re))
+                    }
+                    Ordering::Equal => {
+                        left.next();
+                        right.next();
+                        (lp, f(*le, *re))
+                    }
+                },
+            };
+            if e > 0 {
+                factors.push((p.clone(), e));
+            }
+        }
+        Self { factors }
+    }
+
+    #[must_use]
+    /// Factorization of the greatest common divisor
+    ///
+    /// # Example
+    /// ```
+    /// use facto::{Factoring, Factorization};
+    /// let gcd = 360u64.factor_exponents().gcd(&84u64.factor_exponents());
+    /// assert_eq!(gcd.value(), 12);
+    /// ```
+    pub fn gcd(&self, other: &Self) -> Self
+    where
+        T: Clone,
+    {
+        self.merge(other, std::cmp::min)
+    }
+
+    #[must_use]
+    /// Factorization of the least common multiple
+    pub fn lcm(&self, other: &Self) -> Self
+    where
+        T: Clone,
+    {
+        self.merge(other, std::cmp::max)
+    }
+
+    #[must_use]
+    /// Factorization of the product, or `None` if an exponent exceeds `u32::MAX`
+    pub fn checked_mul(&self, other: &Self) -> Option<Self>
+    where
+        T: Clone,
+    {
+        if other
+            .factors
+            .iter()
+            .all(|(p, e)| self.exponent(p).checked_add(*e).is_some())
+        {
+            Some(self.merge(other, |a, b| a + b))
+        } else {
+            None
+        }
+    }
+
+    #[must_use]
+    /// Factorization of the quotient, or `None` if `other` does not divide this number
+    pub fn checked_div(&self, other: &Self) -> Option<Self>
+    where
+        T: Clone,
+    {
+        if self.is_divisible_by(other) {
+            Some(self.merge(other, |a, b| a - b))
+        } else {
+            None
+        }
+    }
+}
+
+impl<T: Clone> Factorization<T> {
+    #[must_use]
+    /// Factorization of the number raised to the power of `exponent`
+    ///
+    /// # Panics
+    /// If an exponent exceeds `u32::MAX`, see [`Factorization::checked_pow`]
+    pub fn pow(&self, exponent: u32) -> Self {
+        self.checked_pow(exponent).expect("Exponent overflow")
+    }
+
+    #[must_use]
+    /// Factorization of the number raised to the power of `exponent`, or `None` if an exponent exceeds `u32::MAX`
+    pub fn checked_pow(&self, exponent: u32) -> Option<Self> {
+        if exponent == 0 {
+            return Some(Self::one());
+        }
+        let factors = self
+            .factors
+            .iter()
+            .map(|(p, e)| Some((p.clone(), e.checked_mul(exponent)?)))
+            .collect::<Option<_>>()?;
+        Some(Self { factors })
+    }
+
+    #[must_use]
+    /// The number this is the factorization of
+    pub fn value(&self) -> T
+    where
+        T: From<u8> + Mul<Output = T>,
+    {
+        self.factors
+            .iter()
+            .fold(T::from(1), |acc, (p, e)| acc * power(p.clone(), *e))
+    }
+}
+
+impl<T: Ord + Clone> Mul for Factorization<T> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        &self * &rhs
+    }
+}
+
+impl<T: Ord + Clone> Mul for &Factorization<T> {
+    type Output = Factorization<T>;
+
+    /// # Panics
+    /// If an exponent exceeds `u32::MAX`, see [`Factorization::checked_mul`]
+    fn mul(self, rhs: Self) -> Factorization<T> {
+        self.checked_mul(rhs).expect("Exponent overflow")
+    }
+}
+
+impl<T: Ord + Clone> Div for Factorization<T> {
+    type Output = Self;
+
+    /// # Panics
+    /// If `rhs` does not divide `self`, see [`Factorization::checked_div`]
+    fn div(self, rhs: Self) -> Self {
+        self.checked_div(&rhs)
+            .expect("Divisor does not divide the dividend")
+    }
+}
+
+impl<T: Display> Display for Factorization<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.factors.is_empty() {
+            return write!(f, "1");
+        }
+        for (i, (p, e)) in self.factors.iter().enumerate() {
+            if i > 0 {
+                write!(f, " * ")?;
+            }
+            if *e == 1 {
+                write!(f, "{p}")?;
+            } else {
+                write!(f, "{p}^{e}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Error returned when parsing a [`Factorization`] fails
+pub struct ParseFactorizationError {
+    term: String,
+}
+
+impl Display for ParseFactorizationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid factor \"{}\"", self.term)
+    }
+}
+
+impl std::error::Error for ParseFactorizationError {}
+
+impl<T: FromStr + Ord + From<u8>> FromStr for Factorization<T> {
+    type Err = ParseFactorizationError;
+
+    /// Parse a product of powers, like `2^3 * 3 * 5`
+    ///
+    /// Factors may be repeated and unordered, `1` is the empty product and terms with an exponent of zero are dropped.
+    /// Factors below 2 and exponents of a prime summing to more than `u32::MAX` are rejected, the primality of the factors is not checked.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim() == "1" {
+            return Ok(Self::one());
+        }
+        let mut factors = vec![];
+        for term in s.split('*') {
+            let error = || ParseFactorizationError {
+                term: term.trim().to_string(),
+            };
+            let (prime, exponent) = match term.split_once('^') {
+                Some((p, e)) => (p, e.trim().parse::<u32>().map_err(|_| error())?),
+                None => (term, 1),
+            };
+            let prime = prime.trim().parse::<T>().map_err(|_| error())?;
+            if prime < T::from(2) {
+                return Err(error());
+            }
+            factors.push((prime, exponent));
+        }
+        Self::checked_from_factors(factors).ok_or_else(|| ParseFactorizationError {
+            term: s.trim().to_string(),
+        })
+    }
+}
+
+impl<T> IntoIterator for Factorization<T> {
+    type Item = (T, u32);
+    type IntoIter = std::vec::IntoIter<(T, u32)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.factors.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Factorization;
+    use crate::{Factoring, Integer};
+
+    #[test]
+    fn arithmetic() {
+        let a = 360u64.factor_exponents();
+        let b = 84u64.factor_exponents();
+        assert_eq!((&a * &b).value(), 360 * 84);
+        assert_eq!(a.gcd(&b).value(), 12);
+        assert_eq!(a.lcm(&b).value(), 2520);
+        assert_eq!(a.pow(3).value(), 360u64.pow(3));
+        assert_eq!(a.pow(0), Factorization::one());
+        assert_eq!(a.checked_div(&b), None);
+        assert_eq!(a.checked_div(&a.gcd(&b)).unwrap().value(), 30);
+        assert_eq!((a.clone() / a.clone()).value(), 1);
+        assert_eq!(a.exponent(&2), 3);
+        assert_eq!(a.exponent(&7), 0);
+        let big = a.pow(u32::MAX / 3);
+        assert_eq!(big.checked_pow(2), None);
+        assert_eq!(big.checked_mul(&a.pow(2)), None);
+        assert_eq!(big.checked_mul(&b), None);
+        assert_eq!(
+            big.checked_mul(&Factorization::from_primes(vec![7]))
+                .unwrap()
+                .exponent(&7),
+            1
+        );
+        assert_eq!(
+            Factorization::checked_from_factors(vec![(2u64, u32::MAX), (2, 1)]),
+            None
+        );
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!("1".parse::<Factorization<u64>>(), Ok(Factorization::one()));
+        assert_eq!(Factorization::<u64>::one().to_string(), "1");
+        assert_eq!(
+            "5*2 ^ 2 * 2"
+                .parse::<Factorization<u64>>()
+                .unwrap()
+                .factors(),
+            &[(2, 3), (5, 1)]
+        );
+        assert!("2^x * 3".parse::<Factorization<u64>>().is_err());
+        assert!("".parse::<Factorization<u64>>().is_err());
+        assert!("2 * * 3".parse::<Factorization<u64>>().is_err());
+        assert!("1 * 2".parse::<Factorization<u64>>().is_err());
+        assert!("0".parse::<Factorization<u64>>().is_err());
+        assert!("2^4294967295 * 2".parse::<Factorization<u64>>().is_err());
+        assert_eq!(
+            "2^0 * 3".parse::<Factorization<u64>>().unwrap().factors(),
+            &[(3, 1)]
+        );
+    }
+
+    #[test]
+    fn rug() {
+        let n = Integer::from(u128::MAX) * 12u32;
+        let f = n.clone().factor_exponents();
+        assert_eq!(f.value(), n);
+        assert_eq!(f.to_string().parse::<Factorization<Integer>>(), Ok(f));
+    }
+}
@@ -4,19 +4,45 @@
 mod event;
 use std::convert::TryFrom;
 use std::ops::{Add, Div};
+use std::time::Instant;
 
-use event::WrappingFactoringEventSubscriptor;
-pub use event::{EmptyFactoringEventSubscriptor, FactoringEventSubscriptor};
+#[cfg(feature = "tracing")]
+pub use event::TracingSubscriptor;
+pub use event::{
+    CertificationEventSubscriptor, EmptyCertificationEventSubscriptor,
+    EmptyFactoringEventSubscriptor, FactoringAlgorithm, FactoringEventSubscriptor,
+};
+use event::{WrappingCertificationEventSubscriptor, WrappingFactoringEventSubscriptor};
+mod budget;
+pub use budget::{CancellationToken, FactoringBudget, PartialFactorization};
 mod certificate;
-pub use certificate::{LucasCertificate, LucasCertificateElement, LucasCertificateTrait};
+mod factorization;
+pub use factorization::{Factorization, ParseFactorizationError};
+mod recorder;
+pub use certificate::{
+    CertificateElementKind, LucasCertificate, LucasCertificateElement, LucasCertificateTrait,
+};
+pub use recorder::{FactorStatus, FactorTreeNode, FactorTreeRecorder};
+mod session;
+pub use session::FactoringSession;
 
-use crate::factoring::{PollardRho, TrialDivision};
+use crate::factoring::{IterationLimit, NoIterationLimit, PollardRho, TrialDivision};
 use crate::primality::{
     LucasPrimality, LucasPrimalityResult, MillerRabin, MillerRabinCompositeResult,
 };
+use crate::util::BitLength;
 
 use self::certificate::WrappingLucasCertificate;
 
+/// Inclusive bound of the trial division preceding pollard's rho
+const TRIAL_THRESHHOLD: u64 = (1 << 12) - 1;
+
+// <https://en.wikipedia.org/wiki/Miller%E2%80%93Rabin_primality_test#Testing_against_small_sets_of_bases>
+// if n < 18,446,744,073,709,551,616 = 2^64, it is enough to test a = 2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, and 37
+// <http://miller-rabin.appspot.com/>
+// 7	20-04-2011	at least 2^64	2, 325, 9375, 28178, 450775, 9780504, 1795265022	Jim Sinclair
+const DETERMINISTIC_U64_BASES: [u64; 7] = [2, 325, 9375, 28178, 450_775, 9_780_504, 1_795_265_022];
+
 /// Optimized methods of checking and certifying primality
 pub trait Primality: Sized {
     #[allow(clippy::wrong_self_convention)]
@@ -29,7 +55,18 @@ pub trait Primality: Sized {
 /// Factor number into it's prime factors
 pub trait Factoring: Sized {
     /// Factor number, while being notified as soon as any factors are found using the observer "`events`"
-    fn factor_events<T: FactoringEventSubscriptor<Self>>(self, events: T) -> Vec<Self>;
+    ///
+    /// # Example
+    /// ```
+    /// use facto::{Factoring, FactorTreeRecorder};
+    /// let mut tree = FactorTreeRecorder::new();
+    /// assert_eq!(60u64.factor_events(&mut tree), vec![2u64, 2, 3, 5]);
+    /// assert_eq!(tree.root().unwrap().value, 60);
+    /// ```
+    fn factor_events<T: FactoringEventSubscriptor<Self> + ?Sized>(
+        self,
+        events: &mut T,
+    ) -> Vec<Self>;
 
     /// Factor number
     ///
@@ -39,18 +76,54 @@ pub trait Factoring: Sized {
     /// assert_eq!(60u64.factor(), vec![2u64, 2, 3, 5])
     /// ```
     fn factor(self) -> Vec<Self> {
-        Self::factor_events(self, EmptyFactoringEventSubscriptor {})
+        Self::factor_events(self, &mut EmptyFactoringEventSubscriptor {})
+    }
+
+    /// Factor number, returning the distinct prime factors with their exponents
+    ///
+    /// # Example
+    /// ```
+    /// use facto::Factoring;
+    /// assert_eq!(60u64.factor_exponents().to_string(), "2^2 * 3 * 5")
+    /// ```
+    fn factor_exponents(self) -> Factorization<Self>
+    where
+        Self: Ord,
+    {
+        Factorization::from_primes(self.factor())
+    }
+
+    /// Factor number, giving up once `budget` is exhausted
+    ///
+    /// Only the search for factors is limited by `budget`, proving the primality of a found factor is not interrupted.
+    /// In contrast to [`Factoring::factor`], factoring zero terminates, returning zero as an unknown factor.
+    ///
+    /// # Example
+    /// ```
+    /// use facto::{Factoring, FactoringBudget};
+    /// let n = 4_611_686_018_427_387_847u128 * 4_611_686_018_427_387_817;
+    /// let budget = FactoringBudget {
+    ///     max_iterations: Some(1000),
+    ///     ..FactoringBudget::default()
+    /// };
+    /// let partial = (3 * n).factor_with_budget(&budget);
+    /// assert_eq!(partial.primes, vec![3]);
+    /// assert_eq!(partial.composites, vec![n]);
+    /// assert!(!partial.is_complete());
+    /// ```
+    ///
+    /// The default implementation ignores the budget, and returns all factors of [`Factoring::factor`] as primes.
+    fn factor_with_budget(self, _budget: &FactoringBudget) -> PartialFactorization<Self> {
+        PartialFactorization {
+            primes: self.factor(),
+            ..PartialFactorization::default()
+        }
     }
 }
 
 impl Primality for u64 {
     fn is_prime(self) -> bool {
-        // <https://en.wikipedia.org/wiki/Miller%E2%80%93Rabin_primality_test#Testing_against_small_sets_of_bases>
-        // if n < 18,446,744,073,709,551,616 = 2^64, it is enough to test a = 2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, and 37
-        // <http://miller-rabin.appspot.com/>
-        // 7	20-04-2011	at least 2^64	2, 325, 9375, 28178, 450775, 9780504, 1795265022	Jim Sinclair
-        const REQ_PRIMES: [u64; 7] = [2, 325, 9375, 28178, 450_775, 9_780_504, 1_795_265_022];
-        for base in REQ_PRIMES {
+        for base in DETERMINISTIC_U64_BASES {
             if self.miller_rabin(base) == MillerRabinCompositeResult::Composite {
                 return false;
             }
@@ -107,17 +180,34 @@ pub trait CertifiedFactorization: Sized {
     /// use facto::{CertifiedFactorization, PrimalityCertainty};
     /// let mut c = facto::LucasCertificate::default();
     /// let f = 10987081u128.certified_factor(
-    ///     PrimalityCertainty::Certified(&mut c), 
-    ///     facto::EmptyFactoringEventSubscriptor{}
+    ///     PrimalityCertainty::Certified(&mut c),
+    ///     &mut facto::EmptyFactoringEventSubscriptor{}
     /// );
     /// assert_eq!(f, vec![7, 107, 14669]);
     /// assert!(c.elements.binary_search_by_key(&7, |x| x.n).is_ok());
     /// assert!(c.elements.binary_search_by_key(&107, |x| x.n).is_ok());
     /// assert!(c.elements.binary_search_by_key(&14669, |x| x.n).is_ok());
     /// ```
-    fn certified_factor<T>(self, certificate: PrimalityCertainty<Self>, events: T) -> Vec<Self>
+    fn certified_factor<T>(self, certificate: PrimalityCertainty<Self>, events: &mut T) -> Vec<Self>
     where
-        T: FactoringEventSubscriptor<Self>;
+        T: FactoringEventSubscriptor<Self> + ?Sized,
+    {
+        self.certified_factor_events(
+            certificate,
+            events,
+            &mut EmptyCertificationEventSubscriptor {},
+        )
+    }
+
+    /// Same as [`CertifiedFactorization::certified_factor`], but `certification_events` is notified about the certification of the factors
+    fn certified_factor_events<T>(
+        self,
+        certificate: PrimalityCertainty<Self>,
+        events: &mut T,
+        certification_events: &mut dyn CertificationEventSubscriptor<Self>,
+    ) -> Vec<Self>
+    where
+        T: FactoringEventSubscriptor<Self> + ?Sized;
 
     /// Given a certificate, equivalent to [`Primality::generate_lucas_certificate`].
     ///
@@ -127,7 +217,16 @@ pub trait CertifiedFactorization: Sized {
     /// assert!(101u64.certified_prime_check(PrimalityCertainty::Certified(&mut c)));
     /// assert_eq!(c.get_max(), 101u64.generate_lucas_certificate().unwrap().get_max())
     /// ```
-    fn certified_prime_check(self, certificate: PrimalityCertainty<Self>) -> bool;
+    fn certified_prime_check(self, certificate: PrimalityCertainty<Self>) -> bool {
+        self.certified_prime_check_events(certificate, &mut EmptyCertificationEventSubscriptor {})
+    }
+
+    /// Same as [`CertifiedFactorization::certified_prime_check`], but `events` is notified about the progress of the certification
+    fn certified_prime_check_events(
+        self,
+        certificate: PrimalityCertainty<Self>,
+        events: &mut dyn CertificationEventSubscriptor<Self>,
+    ) -> bool;
 }
 
 #[derive(Debug)]
@@ -137,394 +236,622 @@ pub enum PrimalityCertainty<'a, T> {
     Guaranteed,
     /// Same as `Guaranteed`, but also generates the certificate
     Certified(&'a mut dyn LucasCertificateTrait<T>),
+    /// Same as `Certified`, but numbers below 2^64 are attested by the deterministic miller rabin bases
+    /// instead of a lucas subtree, see [`CertificateElementKind::MillerRabin`]
+    ///
+    /// # Example
+    /// ```
+    /// use facto::{CertificateElementKind, CertifiedFactorization, PrimalityCertainty};
+    /// let mut c = facto::LucasCertificate::default();
+    /// assert!(1_000_000_007u128.certified_prime_check(PrimalityCertainty::CertifiedCompact(&mut c)));
+    /// assert_eq!(c.elements.len(), 1);
+    /// assert_eq!(c.elements[0].kind, CertificateElementKind::MillerRabin);
+    /// ```
+    CertifiedCompact(&'a mut dyn LucasCertificateTrait<T>),
 }
 
+/// Split `composite` until all its factors are prime, or `limit` is exhausted
+///
+/// # Returns
+/// Factors known to be composite, and factors of unknown primality
 fn pollard_loop<T, E>(
     composite: T,
     one: &T,
     prime_factors: &mut Vec<T>,
-    mut events: E,
+    events: &mut E,
+    certification_events: &mut dyn CertificationEventSubscriptor<T>,
     mut c: PrimalityCertainty<T>,
-) where
-    T: Clone + PollardRho + Div<Output = T> + CertifiedFactorization + Add<Output = T>,
-    E: FactoringEventSubscriptor<T>,
+    limit: &mut dyn IterationLimit,
+) -> (Vec<T>, Vec<T>)
+where
+    T: Clone + PollardRho + Div<Output = T> + CertifiedFactorization + Add<Output = T> + BitLength,
+    E: FactoringEventSubscriptor<T> + ?Sized,
 {
+    enter_span!(
+        span,
+        "pollard_loop",
+        bits = composite.bit_length(),
+        rho_runs = tracing::field::Empty,
+        outcome = tracing::field::Empty
+    );
+    #[cfg(feature = "tracing")]
+    let mut rho_runs = 0_u64;
     let mut pollard_rho_increment = one.clone();
 
     let two = one.clone() + one.clone();
 
     let mut composite_factors = vec![composite];
+    let mut unknown_factors = vec![];
+    let mut retries = 0;
+    let mut rho_start = Instant::now();
     while let Some(current_factor) = composite_factors.last().cloned() {
-        match current_factor
-            .clone()
-            .pollard_rho(&two, &pollard_rho_increment)
+        if retries == 0 {
+            events.algorithm_started(&current_factor, FactoringAlgorithm::PollardRho);
+            rho_start = Instant::now();
+        }
+        events.pollard_rho_attempt(&current_factor, &two, &pollard_rho_increment);
+        #[cfg(feature = "tracing")]
         {
-            Some(f) => {
-                composite_factors.pop();
-                let other_factor = current_factor.clone() / f.clone();
-                events.factorized(
-                    &current_factor,
-                    &[],
-                    &[],
-                    &[f.clone(), other_factor.clone()],
-                );
-                if f.clone()
-                    .certified_prime_check(clone_primality_certainty(&mut c))
-                {
-                    events.is_prime(&f);
-                    prime_factors.push(f);
+            rho_runs += 1;
+        }
+        let (factor, statistics) =
+            current_factor
+                .clone()
+                .pollard_rho_limited(&two, &pollard_rho_increment, limit);
+        events.pollard_rho_result(
+            &current_factor,
+            &pollard_rho_increment,
+            &statistics,
+            factor.as_ref(),
+        );
+        if let Some(f) = factor {
+            composite_factors.pop();
+            retries = 0;
+            events.algorithm_finished(
+                &current_factor,
+                FactoringAlgorithm::PollardRho,
+                rho_start.elapsed(),
+            );
+            let other_factor = current_factor.clone() / f.clone();
+            events.factorized(
+                &current_factor,
+                &[],
+                &[],
+                &[f.clone(), other_factor.clone()],
+            );
+            for part in [f, other_factor] {
+                if limit.exhausted() {
+                    unknown_factors.push(part);
+                } else if timed_prime_check(
+                    &part,
+                    clone_primality_certainty(&mut c),
+                    events,
+                    certification_events,
+                ) {
+                    events.is_prime(&part);
+                    prime_factors.push(part);
                 } else {
                     // FIXME: coreutils/factor uses `pollard_rho_increment + 1` to check this factor
                     // Maybe we should do too
-                    events.is_composite(&f);
-                    composite_factors.push(f);
+                    events.is_composite(&part);
+                    composite_factors.push(part);
                 }
-                if other_factor
-                    .clone()
-                    .certified_prime_check(clone_primality_certainty(&mut c))
-                {
-                    events.is_prime(&other_factor);
-                    prime_factors.push(other_factor);
-                } else {
-                    events.is_composite(&other_factor);
-                    composite_factors.push(other_factor);
-                }
-            }
-            None => {
-                pollard_rho_increment = pollard_rho_increment + one.clone();
             }
+        } else if limit.exhausted() {
+            events.algorithm_finished(
+                &current_factor,
+                FactoringAlgorithm::PollardRho,
+                rho_start.elapsed(),
+            );
+            break;
+        } else {
+            pollard_rho_increment = pollard_rho_increment + one.clone();
+            retries += 1;
+            events.pollard_rho_retry(&current_factor, &pollard_rho_increment, retries);
         }
     }
+    record_span!(span, "rho_runs", rho_runs);
+    record_span!(
+        span,
+        "outcome",
+        if composite_factors.is_empty() && unknown_factors.is_empty() {
+            "complete"
+        } else {
+            "exhausted"
+        }
+    );
+    (composite_factors, unknown_factors)
+}
+
+/// Trial division followed by pollard's rho on the remaining composite, until `limit` is exhausted
+fn budgeted_factor<T, E>(
+    n: &T,
+    trial_threshold: &T,
+    one: &T,
+    mut certificate: PrimalityCertainty<T>,
+    events: &mut E,
+    certification_events: &mut dyn CertificationEventSubscriptor<T>,
+    limit: &mut dyn IterationLimit,
+) -> PartialFactorization<T>
+where
+    T: Ord
+        + BitLength
+        + TrialDivision
+        + PollardRho
+        + Div<Output = T>
+        + CertifiedFactorization
+        + Add<Output = T>,
+    E: FactoringEventSubscriptor<T> + ?Sized,
+{
+    let (mut pre_processed, exhaustive) = timed_trial_division(n, trial_threshold, events);
+    if !matches!(certificate, PrimalityCertainty::Guaranteed) {
+        for prime_factor in &pre_processed[..pre_processed.len().saturating_sub(1)] {
+            prime_factor.clone().certified_prime_check_events(
+                clone_primality_certainty(&mut certificate),
+                certification_events,
+            );
+        }
+        if exhaustive {
+            pre_processed
+                .last()
+                .unwrap()
+                .clone()
+                .certified_prime_check_events(
+                    clone_primality_certainty(&mut certificate),
+                    certification_events,
+                );
+        }
+    }
+    if exhaustive
+        || timed_prime_check(
+            pre_processed.last().unwrap(),
+            clone_primality_certainty(&mut certificate),
+            events,
+            certification_events,
+        )
+    {
+        match pre_processed.len() {
+            0 => (),
+            1 => events.is_prime(n),
+            _ => events.factorized(n, &pre_processed, &[], &[]),
+        }
+        return PartialFactorization {
+            primes: pre_processed,
+            ..PartialFactorization::default()
+        };
+    }
+
+    let composite_factor = pre_processed.pop().unwrap();
+    let mut prime_factors = pre_processed;
+    if prime_factors.is_empty() {
+        events.is_composite(n);
+    } else {
+        events.factorized(n, &prime_factors, &[composite_factor.clone()], &[]);
+    }
+
+    let (composites, unknown) = pollard_loop(
+        composite_factor,
+        one,
+        &mut prime_factors,
+        events,
+        certification_events,
+        certificate,
+        limit,
+    );
+
+    prime_factors.sort_unstable();
+    PartialFactorization {
+        primes: prime_factors,
+        composites,
+        unknown,
+    }
+}
+
+fn timed_trial_division<T, E>(n: &T, inclusive_bound: &T, events: &mut E) -> (Vec<T>, bool)
+where
+    T: TrialDivision,
+    E: FactoringEventSubscriptor<T> + ?Sized,
+{
+    events.algorithm_started(n, FactoringAlgorithm::TrialDivision);
+    let start = Instant::now();
+    let result = n.clone().trial_division(inclusive_bound);
+    events.algorithm_finished(n, FactoringAlgorithm::TrialDivision, start.elapsed());
+    result
+}
+
+fn timed_prime_check<T, E>(
+    n: &T,
+    c: PrimalityCertainty<T>,
+    events: &mut E,
+    certification_events: &mut dyn CertificationEventSubscriptor<T>,
+) -> bool
+where
+    T: Clone + CertifiedFactorization,
+    E: FactoringEventSubscriptor<T> + ?Sized,
+{
+    events.algorithm_started(n, FactoringAlgorithm::PrimalityCheck);
+    let start = Instant::now();
+    let result = n
+        .clone()
+        .certified_prime_check_events(c, certification_events);
+    events.algorithm_finished(n, FactoringAlgorithm::PrimalityCheck, start.elapsed());
+    result
 }
 
 fn clone_primality_certainty<'a, T>(x: &'a mut PrimalityCertainty<T>) -> PrimalityCertainty<'a, T> {
     match x {
         PrimalityCertainty::Guaranteed => PrimalityCertainty::Guaranteed,
         PrimalityCertainty::Certified(ref mut x) => PrimalityCertainty::Certified(*x),
+        PrimalityCertainty::CertifiedCompact(ref mut x) => PrimalityCertainty::CertifiedCompact(*x),
     }
 }
 
-impl CertifiedFactorization for u64 {
-    fn certified_factor<T: FactoringEventSubscriptor<Self>>(
-        self,
-        mut certificate: PrimalityCertainty<Self>,
-        mut events: T,
-    ) -> Vec<Self> {
-        const TRIAL_THRESHHOLD: u64 = (1 << 12) - 1;
+/// Notify `events` about the start and the result of the primality certification `check` of `n`
+fn observed_prime_check<T>(
+    n: &T,
+    events: &mut dyn CertificationEventSubscriptor<T>,
+    check: impl FnOnce(&mut dyn CertificationEventSubscriptor<T>) -> bool,
+) -> bool {
+    events.certification_started(n);
+    let start = Instant::now();
+    let prime = check(events);
+    events.certification_finished(n, prime, start.elapsed());
+    prime
+}
 
-        let (mut pre_processed, exhaustive) = self.trial_division(&TRIAL_THRESHHOLD);
-        if let PrimalityCertainty::Certified(ref mut x) = certificate {
-            for prime_factor in &pre_processed[..pre_processed.len().saturating_sub(1)] {
-                prime_factor.certified_prime_check(PrimalityCertainty::Certified(*x));
-            }
-            if exhaustive {
-                pre_processed
-                    .last()
-                    .unwrap()
-                    .certified_prime_check(PrimalityCertainty::Certified(*x));
-            }
-        }
-        if exhaustive
-            || pre_processed
-                .last()
-                .unwrap()
-                .certified_prime_check(clone_primality_certainty(&mut certificate))
-        {
-            return pre_processed;
-        }
+fn push_element<T>(
+    certificate: &mut dyn LucasCertificateTrait<T>,
+    events: &mut dyn CertificationEventSubscriptor<T>,
+    element: LucasCertificateElement<T>,
+) {
+    events.element_added(&element);
+    certificate.push(element);
+}
+
+/// Unique prime divisors of `n - 1`, which are certified using `c`
+fn n_minus_one_divisors<T>(
+    n: &T,
+    n_minus_1: T,
+    c: PrimalityCertainty<T>,
+    events: &mut dyn CertificationEventSubscriptor<T>,
+) -> Vec<T>
+where
+    T: CertifiedFactorization + PartialEq,
+{
+    events.n_minus_one_factoring_started(n);
+    let start = Instant::now();
+    let mut divisors =
+        n_minus_1.certified_factor_events(c, &mut EmptyFactoringEventSubscriptor {}, events);
+    divisors.dedup();
+    events.n_minus_one_factored(n, &divisors, start.elapsed());
+    divisors
+}
 
-        let composite_factor = pre_processed.pop().unwrap();
-        let mut prime_factors = pre_processed;
-        if !prime_factors.is_empty() {
-            events.factorized(&self, &prime_factors, &[composite_factor], &[]);
+/// Pass a certificate of a wider type to the implementation of a narrower type, `wrapper` holding the adapter
+fn wrap_primality_certainty<'a, 'b, F, T>(
+    certainty: PrimalityCertainty<'a, T>,
+    wrapper: &'b mut Option<WrappingLucasCertificate<'a, F, T>>,
+) -> PrimalityCertainty<'b, F>
+where
+    F: Clone + TryFrom<T> + std::fmt::Debug,
+    T: From<F> + Ord + Clone + std::fmt::Debug,
+{
+    match certainty {
+        PrimalityCertainty::Guaranteed => PrimalityCertainty::Guaranteed,
+        PrimalityCertainty::Certified(p) => {
+            PrimalityCertainty::Certified(wrapper.insert(WrappingLucasCertificate::from(p)))
         }
+        PrimalityCertainty::CertifiedCompact(p) => {
+            PrimalityCertainty::CertifiedCompact(wrapper.insert(WrappingLucasCertificate::from(p)))
+        }
+    }
+}
 
-        pollard_loop(
-            composite_factor,
+impl CertifiedFactorization for u64 {
+    fn certified_factor_events<T: FactoringEventSubscriptor<Self> + ?Sized>(
+        self,
+        certificate: PrimalityCertainty<Self>,
+        events: &mut T,
+        certification_events: &mut dyn CertificationEventSubscriptor<Self>,
+    ) -> Vec<Self> {
+        enter_span!(
+            span,
+            "certified_factor",
+            bits = self.bit_length(),
+            factors = tracing::field::Empty
+        );
+        let factors = budgeted_factor(
+            &self,
+            &TRIAL_THRESHHOLD,
             &1,
-            &mut prime_factors,
-            events,
             certificate,
-        );
-
-        prime_factors.sort_unstable();
-        prime_factors
+            events,
+            certification_events,
+            &mut NoIterationLimit,
+        )
+        .primes;
+        record_span!(span, "factors", factors.len());
+        factors
     }
 
-    fn certified_prime_check(self, certificate: PrimalityCertainty<Self>) -> bool {
-        let certificate = if let PrimalityCertainty::Certified(certificate) = certificate {
-            certificate
-        } else {
-            return self.is_prime();
-        };
-        if certificate.contains(&self) {
-            return true;
-        }
-        if self == 2 {
-            if !certificate.contains(&self) {
-                certificate.push(LucasCertificateElement {
-                    n: self,
-                    base: 1,
-                    unique_prime_divisors: vec![1],
-                });
+    fn certified_prime_check_events(
+        self,
+        certificate: PrimalityCertainty<Self>,
+        events: &mut dyn CertificationEventSubscriptor<Self>,
+    ) -> bool {
+        observed_prime_check(&self, events, |events| {
+            let (certificate, compact) = match certificate {
+                PrimalityCertainty::Guaranteed => return self.is_prime(),
+                PrimalityCertainty::Certified(certificate) => (certificate, false),
+                PrimalityCertainty::CertifiedCompact(certificate) => (certificate, true),
+            };
+            if certificate.contains(&self) {
+                return true;
+            }
+            if self == 2 {
+                if !certificate.contains(&self) {
+                    push_element(
+                        certificate,
+                        events,
+                        LucasCertificateElement {
+                            n: self,
+                            base: 1,
+                            unique_prime_divisors: vec![1],
+                            kind: CertificateElementKind::Lucas,
+                        },
+                    );
+                }
+                return true;
+            };
+            if !self.is_prime() {
+                return false;
+            }
+            if compact {
+                push_element(
+                    certificate,
+                    events,
+                    LucasCertificateElement {
+                        n: self,
+                        base: 0,
+                        unique_prime_divisors: vec![],
+                        kind: CertificateElementKind::MillerRabin,
+                    },
+                );
+                return true;
             }
-            return true;
-        };
-        if !self.is_prime() {
-            return false;
-        }
 
-        let mut factors = (self - 1).certified_factor(
-            PrimalityCertainty::Certified(certificate),
-            EmptyFactoringEventSubscriptor {},
-        );
-        factors.dedup();
-        let factors = factors;
+            let factors = n_minus_one_divisors(
+                &self,
+                self - 1,
+                PrimalityCertainty::Certified(certificate),
+                events,
+            );
 
-        let mut witness = 0;
-        for base in 2.. {
-            match self.lucas_primality_test(&factors, base){
+            let mut witness = 0;
+            for base in 2.. {
+                match self.lucas_primality_test(&factors, base){
                 LucasPrimalityResult::Prime => {
                     witness = base;
                     break;
                 },
                 LucasPrimalityResult::Composite => panic!("We already checked for compositeness, we should never reach this. Miller rabin bases wrong?"),
-                LucasPrimalityResult::Unknown => (),
+                LucasPrimalityResult::Unknown => events.witness_failed(&self, &base),
             }
-        }
-        certificate.push(LucasCertificateElement {
-            n: self,
-            base: witness,
-            unique_prime_divisors: factors,
-        });
-        true
+            }
+            push_element(
+                certificate,
+                events,
+                LucasCertificateElement {
+                    n: self,
+                    base: witness,
+                    unique_prime_divisors: factors,
+                    kind: CertificateElementKind::Lucas,
+                },
+            );
+            true
+        })
     }
 }
 
 impl CertifiedFactorization for u128 {
-    fn certified_factor<T: FactoringEventSubscriptor<Self>>(
+    fn certified_factor_events<T: FactoringEventSubscriptor<Self> + ?Sized>(
         self,
-        mut certificate: PrimalityCertainty<Self>,
-        mut events: T,
+        certificate: PrimalityCertainty<Self>,
+        events: &mut T,
+        certification_events: &mut dyn CertificationEventSubscriptor<Self>,
     ) -> Vec<Self> {
-        const TRIAL_THRESHHOLD: u128 = (1 << 12) - 1;
+        enter_span!(
+            span,
+            "certified_factor",
+            bits = self.bit_length(),
+            factors = tracing::field::Empty
+        );
+        let factors = if let Ok(x) = u64::try_from(self) {
+            let mut o = None;
+            let w_c = wrap_primality_certainty::<u64, Self>(certificate, &mut o);
+            let r = x.certified_factor_events(
+                w_c,
+                &mut WrappingFactoringEventSubscriptor::new(events),
+                &mut WrappingCertificationEventSubscriptor::new(certification_events),
+            );
+            r.into_iter().map(Self::from).collect()
+        } else {
+            budgeted_factor(
+                &self,
+                &Self::from(TRIAL_THRESHHOLD),
+                &1,
+                certificate,
+                events,
+                certification_events,
+                &mut NoIterationLimit,
+            )
+            .primes
+        };
+        record_span!(span, "factors", factors.len());
+        factors
+    }
 
+    fn certified_prime_check_events(
+        self,
+        mut certificate: PrimalityCertainty<Self>,
+        events: &mut dyn CertificationEventSubscriptor<Self>,
+    ) -> bool {
         if let Ok(x) = u64::try_from(self) {
-            let mut o;
-            let w_c = match certificate {
-                PrimalityCertainty::Guaranteed => PrimalityCertainty::Guaranteed,
-                PrimalityCertainty::Certified(p) => {
-                    o = Some(WrappingLucasCertificate::<u64, Self>::from(p));
-                    PrimalityCertainty::Certified(o.as_mut().unwrap())
-                }
-            };
-            let r = x.certified_factor(w_c, WrappingFactoringEventSubscriptor::new(events));
-            return r.into_iter().map(Self::from).collect();
-        }
-
-        let (mut pre_processed, exhaustive) = self.trial_division(&TRIAL_THRESHHOLD);
-        if let PrimalityCertainty::Certified(ref mut certificate) = certificate {
-            for prime_factor in &pre_processed[..pre_processed.len().saturating_sub(1)] {
-                prime_factor.certified_prime_check(PrimalityCertainty::Certified(*certificate));
-            }
-            if exhaustive {
-                pre_processed
-                    .last()
-                    .unwrap()
-                    .certified_prime_check(PrimalityCertainty::Certified(*certificate));
-            }
-        }
-        if exhaustive
-            || pre_processed
-                .last()
-                .unwrap()
-                .certified_prime_check(clone_primality_certainty(&mut certificate))
-        {
-            return pre_processed;
-        }
-
-        let composite_factor = pre_processed.pop().unwrap();
-        let mut prime_factors = pre_processed;
-        if !prime_factors.is_empty() {
-            events.factorized(&self, &prime_factors, &[composite_factor], &[]);
+            let mut o = None;
+            let w_c = wrap_primality_certainty::<u64, Self>(certificate, &mut o);
+            return x.certified_prime_check_events(
+                w_c,
+                &mut WrappingCertificationEventSubscriptor::new(events),
+            );
         }
 
-        pollard_loop(
-            composite_factor,
-            &1,
-            &mut prime_factors,
-            events,
-            certificate,
-        );
-
-        prime_factors.sort_unstable();
-        prime_factors
-    }
-
-    fn certified_prime_check(self, mut certificate: PrimalityCertainty<Self>) -> bool {
-        if self % 2 == 0 {
-            return if self == 2 {
-                if let PrimalityCertainty::Certified(certificate) = certificate {
-                    if !certificate.contains(&self) {
-                        certificate.push(LucasCertificateElement {
-                            n: self,
-                            base: 1,
-                            unique_prime_divisors: vec![1],
-                        });
+        observed_prime_check(&self.clone(), events, move |events| {
+            if self % 2 == 0 {
+                return if self == 2 {
+                    if let PrimalityCertainty::Certified(certificate)
+                    | PrimalityCertainty::CertifiedCompact(certificate) = certificate
+                    {
+                        if !certificate.contains(&self) {
+                            push_element(
+                                certificate,
+                                events,
+                                LucasCertificateElement {
+                                    n: self,
+                                    base: 1,
+                                    unique_prime_divisors: vec![1],
+                                    kind: CertificateElementKind::Lucas,
+                                },
+                            );
+                        }
                     }
-                }
-                true
-            } else {
-                false
+                    true
+                } else {
+                    false
+                };
             };
-        };
 
-        if let Ok(x) = u64::try_from(self) {
-            let mut o;
-            let w_c = match certificate {
-                PrimalityCertainty::Guaranteed => PrimalityCertainty::Guaranteed,
-                PrimalityCertainty::Certified(p) => {
-                    o = Some(WrappingLucasCertificate::<u64, Self>::from(p));
-                    PrimalityCertainty::Certified(o.as_mut().unwrap())
-                }
+            let n_minus_one_unique_prime_factors = match delayed_lucas(
+                &self,
+                self - 1,
+                clone_primality_certainty(&mut certificate),
+                2u128..=20,
+                events,
+            ) {
+                (true, None) => return true,
+                (true, Some(x)) => x,
+                (false, _) => return false,
             };
-            return x.certified_prime_check(w_c);
-        }
-
-        let n_minus_one_unique_prime_factors = match delayed_lucas(
-            &self,
-            self - 1,
-            clone_primality_certainty(&mut certificate),
-            2u128..=20,
-        ) {
-            (true, None) => return true,
-            (true, Some(x)) => x,
-            (false, _) => return false,
-        };
 
-        miller_lucas_loop(
-            21,
-            self,
-            certificate,
-            &n_minus_one_unique_prime_factors,
-            |x| *x += 1,
-        )
+            miller_lucas_loop(
+                21,
+                self,
+                certificate,
+                &n_minus_one_unique_prime_factors,
+                |x| *x += 1,
+                events,
+            )
+        })
     }
 }
 
 impl CertifiedFactorization for rug::Integer {
-    fn certified_factor<T: FactoringEventSubscriptor<Self>>(
+    fn certified_factor_events<T: FactoringEventSubscriptor<Self> + ?Sized>(
         self,
-        mut certificate: PrimalityCertainty<Self>,
-        mut events: T,
+        certificate: PrimalityCertainty<Self>,
+        events: &mut T,
+        certification_events: &mut dyn CertificationEventSubscriptor<Self>,
     ) -> Vec<Self> {
-        const TRIAL_THRESHHOLD: u128 = (1 << 12) - 1;
+        enter_span!(
+            span,
+            "certified_factor",
+            bits = self.bit_length(),
+            factors = tracing::field::Empty
+        );
+        let factors = if let Some(x) = self.to_u128() {
+            let mut o = None;
+            let w_c = wrap_primality_certainty::<u128, Self>(certificate, &mut o);
+            let r = x.certified_factor_events(
+                w_c,
+                &mut WrappingFactoringEventSubscriptor::new(events),
+                &mut WrappingCertificationEventSubscriptor::new(certification_events),
+            );
+            r.into_iter().map(Self::from).collect()
+        } else {
+            budgeted_factor(
+                &self,
+                &Self::from(TRIAL_THRESHHOLD),
+                &Self::from(1),
+                certificate,
+                events,
+                certification_events,
+                &mut NoIterationLimit,
+            )
+            .primes
+        };
+        record_span!(span, "factors", factors.len());
+        factors
+    }
 
+    fn certified_prime_check_events(
+        self,
+        mut certificate: PrimalityCertainty<Self>,
+        events: &mut dyn CertificationEventSubscriptor<Self>,
+    ) -> bool {
         if let Some(x) = self.to_u128() {
-            let mut o;
-            let w_c = match certificate {
-                PrimalityCertainty::Guaranteed => PrimalityCertainty::Guaranteed,
-                PrimalityCertainty::Certified(p) => {
-                    o = Some(WrappingLucasCertificate::<u128, Self>::from(p));
-                    PrimalityCertainty::Certified(o.as_mut().unwrap())
-                }
-            };
-            let r = x.certified_factor(w_c, WrappingFactoringEventSubscriptor::new(events));
-            return r.into_iter().map(Self::from).collect();
-        }
-
-        let (mut pre_processed, exhaustive) = self.clone().trial_division(&TRIAL_THRESHHOLD.into());
-        if let PrimalityCertainty::Certified(ref mut certificate) = certificate {
-            for prime_factor in &pre_processed[..pre_processed.len().saturating_sub(1)] {
-                prime_factor
-                    .clone()
-                    .certified_prime_check(PrimalityCertainty::Certified(*certificate));
-            }
-            if exhaustive {
-                pre_processed
-                    .last()
-                    .unwrap()
-                    .clone()
-                    .certified_prime_check(PrimalityCertainty::Certified(*certificate));
-            }
-        }
-        if exhaustive
-            || pre_processed
-                .last()
-                .unwrap()
-                .clone()
-                .certified_prime_check(clone_primality_certainty(&mut certificate))
-        {
-            return pre_processed;
+            let mut o = None;
+            let w_c = wrap_primality_certainty::<u128, Self>(certificate, &mut o);
+            return x.certified_prime_check_events(
+                w_c,
+                &mut WrappingCertificationEventSubscriptor::new(events),
+            );
         }
 
-        let composite_factor = pre_processed.pop().unwrap();
-        let mut prime_factors = pre_processed;
-
-        if !prime_factors.is_empty() {
-            events.factorized(&self, &prime_factors, &[composite_factor.clone()], &[]);
-        }
-
-        pollard_loop(
-            composite_factor,
-            &1.into(),
-            &mut prime_factors,
-            events,
-            certificate,
-        );
-
-        prime_factors.sort_unstable();
-        prime_factors
-    }
-
-    fn certified_prime_check(self, mut certificate: PrimalityCertainty<Self>) -> bool {
-        if self.clone() % 2 == 0 {
-            return if self == 2 {
-                if let PrimalityCertainty::Certified(certificate) = certificate {
-                    if !certificate.contains(&self) {
-                        certificate.push(LucasCertificateElement {
-                            n: self,
-                            base: 1.into(),
-                            unique_prime_divisors: vec![1.into()],
-                        });
+        observed_prime_check(&self.clone(), events, move |events| {
+            if self.clone() % 2 == 0 {
+                return if self == 2 {
+                    if let PrimalityCertainty::Certified(certificate)
+                    | PrimalityCertainty::CertifiedCompact(certificate) = certificate
+                    {
+                        if !certificate.contains(&self) {
+                            push_element(
+                                certificate,
+                                events,
+                                LucasCertificateElement {
+                                    n: self,
+                                    base: 1.into(),
+                                    unique_prime_divisors: vec![1.into()],
+                                    kind: CertificateElementKind::Lucas,
+                                },
+                            );
+                        }
                     }
-                }
-                true
-            } else {
-                false
+                    true
+                } else {
+                    false
+                };
             };
-        };
 
-        if let Some(x) = self.to_u128() {
-            let mut o;
-            let w_c = match certificate {
-                PrimalityCertainty::Guaranteed => PrimalityCertainty::Guaranteed,
-                PrimalityCertainty::Certified(p) => {
-                    o = Some(WrappingLucasCertificate::<u128, Self>::from(p));
-                    PrimalityCertainty::Certified(o.as_mut().unwrap())
-                }
+            let n_minus_one_unique_prime_factors = match delayed_lucas(
+                &self,
+                self.clone() - 1,
+                clone_primality_certainty(&mut certificate),
+                (2..=20).map(Self::from),
+                events,
+            ) {
+                (true, None) => return true,
+                (true, Some(x)) => x,
+                (false, _) => return false,
             };
-            return x.certified_prime_check(w_c);
-        }
-
-        let n_minus_one_unique_prime_factors = match delayed_lucas(
-            &self,
-            self.clone() - 1,
-            clone_primality_certainty(&mut certificate),
-            (2..=20).map(Self::from),
-        ) {
-            (true, None) => return true,
-            (true, Some(x)) => x,
-            (false, _) => return false,
-        };
 
-        miller_lucas_loop(
-            21.into(),
-            self,
-            certificate,
-            &n_minus_one_unique_prime_factors,
-            |x| *x += 1,
-        )
+            miller_lucas_loop(
+                21.into(),
+                self,
+                certificate,
+                &n_minus_one_unique_prime_factors,
+                |x| *x += 1,
+                events,
+            )
+        })
     }
 }
 
@@ -534,39 +861,98 @@ fn miller_lucas_loop<T, IncFn>(
     mut c: PrimalityCertainty<T>,
     factors: &[T],
     increment_fn: IncFn,
+    events: &mut dyn CertificationEventSubscriptor<T>,
 ) -> bool
 where
-    T: Clone + MillerRabin + LucasPrimality,
+    T: Clone + MillerRabin + LucasPrimality + BitLength,
     IncFn: Fn(&mut T),
 {
-    loop {
+    enter_span!(
+        span,
+        "miller_lucas_loop",
+        bits = n.bit_length(),
+        bases = tracing::field::Empty,
+        outcome = tracing::field::Empty
+    );
+    #[cfg(feature = "tracing")]
+    let mut bases = 0_u64;
+    let prime = loop {
+        #[cfg(feature = "tracing")]
+        {
+            bases += 1;
+        }
         match n.clone().miller_rabin(start_base.clone()) {
-            MillerRabinCompositeResult::Composite => return false,
+            MillerRabinCompositeResult::Composite => break false,
             MillerRabinCompositeResult::MaybePrime => (),
         };
         match n.clone().lucas_primality_test(factors, start_base.clone()) {
             LucasPrimalityResult::Prime => {
-                if let PrimalityCertainty::Certified(ref mut certificate) = c {
-                    certificate.push(LucasCertificateElement {
-                        n,
-                        base: start_base,
-                        unique_prime_divisors: factors.to_vec(),
-                    });
+                if let PrimalityCertainty::Certified(ref mut certificate)
+                | PrimalityCertainty::CertifiedCompact(ref mut certificate) = c
+                {
+                    push_element(
+                        *certificate,
+                        events,
+                        LucasCertificateElement {
+                            n,
+                            base: start_base,
+                            unique_prime_divisors: factors.to_vec(),
+                            kind: CertificateElementKind::Lucas,
+                        },
+                    );
                 }
-                return true;
+                break true;
             }
-            LucasPrimalityResult::Composite => return false,
-            LucasPrimalityResult::Unknown => (),
+            LucasPrimalityResult::Composite => break false,
+            LucasPrimalityResult::Unknown => events.witness_failed(&n, &start_base),
         }
         increment_fn(&mut start_base);
-    }
+    };
+    record_span!(span, "bases", bases);
+    record_span!(span, "outcome", if prime { "prime" } else { "composite" });
+    prime
 }
 
+/// Check the primality of `n` using the bases in `range`, factoring `n - 1` only if miller rabin did not show `n` to be composite
+///
+/// # Returns
+/// Primality of `n`, and the unique prime factors of `n - 1` if none of the bases could prove the primality
 fn delayed_lucas<T, R>(
+    n: &T,
+    n_minus_1: T,
+    c: PrimalityCertainty<T>,
+    range: R,
+    events: &mut dyn CertificationEventSubscriptor<T>,
+) -> (bool, Option<Vec<T>>)
+where
+    T: Clone + CertifiedFactorization + MillerRabin + LucasPrimality + PartialEq + BitLength,
+    R: std::iter::Iterator<Item = T> + Clone,
+{
+    enter_span!(
+        span,
+        "delayed_lucas",
+        bits = n.bit_length(),
+        outcome = tracing::field::Empty
+    );
+    let result = untraced_delayed_lucas(n, n_minus_1, c, range, events);
+    record_span!(
+        span,
+        "outcome",
+        match result {
+            (false, _) => "composite",
+            (true, None) => "prime",
+            (true, Some(_)) => "undecided",
+        }
+    );
+    result
+}
+
+fn untraced_delayed_lucas<T, R>(
     n: &T,
     n_minus_1: T,
     mut c: PrimalityCertainty<T>,
     range: R,
+    events: &mut dyn CertificationEventSubscriptor<T>,
 ) -> (bool, Option<Vec<T>>)
 where
     T: Clone + CertifiedFactorization + MillerRabin + LucasPrimality + PartialEq,
@@ -579,12 +965,8 @@ where
             MillerRabinCompositeResult::MaybePrime => (),
         }
     }
-    let mut n_minus_one_unique_prime_factors = T::certified_factor(
-        n_minus_1,
-        clone_primality_certainty(&mut c),
-        EmptyFactoringEventSubscriptor {},
-    );
-    n_minus_one_unique_prime_factors.dedup();
+    let n_minus_one_unique_prime_factors =
+        n_minus_one_divisors(n, n_minus_1, clone_primality_certainty(&mut c), events);
 
     for pre_base in range {
         match n
@@ -592,38 +974,103 @@ where
             .lucas_primality_test(&n_minus_one_unique_prime_factors, pre_base.clone())
         {
             LucasPrimalityResult::Prime => {
-                if let PrimalityCertainty::Certified(certificate) = c {
-                    certificate.push(LucasCertificateElement {
-                        n: n.clone(),
-                        base: pre_base,
-                        unique_prime_divisors: n_minus_one_unique_prime_factors,
-                    });
+                if let PrimalityCertainty::Certified(certificate)
+                | PrimalityCertainty::CertifiedCompact(certificate) = c
+                {
+                    push_element(
+                        certificate,
+                        events,
+                        LucasCertificateElement {
+                            n: n.clone(),
+                            base: pre_base,
+                            unique_prime_divisors: n_minus_one_unique_prime_factors,
+                            kind: CertificateElementKind::Lucas,
+                        },
+                    );
                 }
                 return (true, None);
             }
             LucasPrimalityResult::Composite => return (false, None),
-            LucasPrimalityResult::Unknown => (),
+            LucasPrimalityResult::Unknown => events.witness_failed(n, &pre_base),
         }
     }
     (true, Some(n_minus_one_unique_prime_factors))
 }
 
 impl Factoring for u64 {
-    fn factor_events<T: FactoringEventSubscriptor<Self>>(self, events: T) -> Vec<Self> {
+    fn factor_events<T: FactoringEventSubscriptor<Self> + ?Sized>(
+        self,
+        events: &mut T,
+    ) -> Vec<Self> {
         self.certified_factor(PrimalityCertainty::Guaranteed, events)
     }
+
+    fn factor_with_budget(self, budget: &FactoringBudget) -> PartialFactorization<Self> {
+        match self {
+            0 => PartialFactorization {
+                unknown: vec![0],
+                ..PartialFactorization::default()
+            },
+            1 => PartialFactorization::default(),
+            _ => budgeted_factor(
+                &self,
+                &TRIAL_THRESHHOLD,
+                &1,
+                PrimalityCertainty::Guaranteed,
+                &mut EmptyFactoringEventSubscriptor {},
+                &mut EmptyCertificationEventSubscriptor {},
+                &mut budget.tracker(),
+            ),
+        }
+    }
 }
 
 impl Factoring for u128 {
-    fn factor_events<T: FactoringEventSubscriptor<Self>>(self, events: T) -> Vec<Self> {
+    fn factor_events<T: FactoringEventSubscriptor<Self> + ?Sized>(
+        self,
+        events: &mut T,
+    ) -> Vec<Self> {
         self.certified_factor(PrimalityCertainty::Guaranteed, events)
     }
+
+    fn factor_with_budget(self, budget: &FactoringBudget) -> PartialFactorization<Self> {
+        if let Ok(x) = u64::try_from(self) {
+            return x.factor_with_budget(budget).convert();
+        }
+        budgeted_factor(
+            &self,
+            &Self::from(TRIAL_THRESHHOLD),
+            &1,
+            PrimalityCertainty::Guaranteed,
+            &mut EmptyFactoringEventSubscriptor {},
+            &mut EmptyCertificationEventSubscriptor {},
+            &mut budget.tracker(),
+        )
+    }
 }
 
 impl Factoring for rug::Integer {
-    fn factor_events<T: FactoringEventSubscriptor<Self>>(self, events: T) -> Vec<Self> {
+    fn factor_events<T: FactoringEventSubscriptor<Self> + ?Sized>(
+        self,
+        events: &mut T,
+    ) -> Vec<Self> {
         self.certified_factor(PrimalityCertainty::Guaranteed, events)
     }
+
+    fn factor_with_budget(self, budget: &FactoringBudget) -> PartialFactorization<Self> {
+        if let Some(x) = self.to_u128() {
+            return x.factor_with_budget(budget).convert();
+        }
+        budgeted_factor(
+            &self,
+            &Self::from(TRIAL_THRESHHOLD),
+            &Self::from(1),
+            PrimalityCertainty::Guaranteed,
+            &mut EmptyFactoringEventSubscriptor {},
+            &mut EmptyCertificationEventSubscriptor {},
+            &mut budget.tracker(),
+        )
+    }
 }
 
 #[test]
@@ -633,11 +1080,193 @@ fn bla() {
 
 #[cfg(test)]
 mod tests {
-    use super::Primality;
+    use super::{
+        CertificationEventSubscriptor, CertifiedFactorization, Factoring, FactoringAlgorithm,
+        FactoringEventSubscriptor, LucasCertificate, LucasCertificateElement, Primality,
+        PrimalityCertainty,
+    };
+    use crate::factoring::PollardRhoStatistics;
+
     #[test]
     fn primality() {
         assert!(407_521_u64.is_prime());
         assert!(2u128.is_prime());
         assert!(7u128.is_prime());
     }
+
+    #[derive(Default)]
+    struct AlgorithmLog {
+        started: Vec<(u128, FactoringAlgorithm)>,
+        finished: Vec<(u128, FactoringAlgorithm)>,
+        rho_attempts: Vec<(u128, u128)>,
+        rho_iterations: u64,
+        rho_factors: Vec<u128>,
+    }
+
+    impl FactoringEventSubscriptor<u128> for AlgorithmLog {
+        fn factorized(
+            &mut self,
+            _n: &u128,
+            _primes: &[u128],
+            _composites: &[u128],
+            _unknown: &[u128],
+        ) {
+        }
+        fn is_prime(&mut self, _n: &u128) {}
+        fn is_composite(&mut self, _n: &u128) {}
+
+        fn algorithm_started(&mut self, n: &u128, algorithm: FactoringAlgorithm) {
+            self.started.push((*n, algorithm));
+        }
+        fn algorithm_finished(
+            &mut self,
+            n: &u128,
+            algorithm: FactoringAlgorithm,
+            _elapsed: std::time::Duration,
+        ) {
+            self.finished.push((*n, algorithm));
+        }
+        fn pollard_rho_attempt(&mut self, n: &u128, _seed: &u128, increment: &u128) {
+            self.rho_attempts.push((*n, *increment));
+        }
+        fn pollard_rho_result(
+            &mut self,
+            _n: &u128,
+            _increment: &u128,
+            statistics: &PollardRhoStatistics,
+            factor: Option<&u128>,
+        ) {
+            self.rho_iterations += statistics.iterations;
+            self.rho_factors.extend(factor);
+        }
+    }
+
+    #[test]
+    fn algorithm_events() {
+        const P: u128 = 4_294_967_279;
+        const Q: u128 = 4_294_967_291;
+        let mut log = AlgorithmLog::default();
+        assert_eq!((P * Q * 2).factor_events(&mut log), vec![2, P, Q]);
+        assert_eq!(
+            log.started,
+            vec![
+                (P * Q * 2, FactoringAlgorithm::TrialDivision),
+                (P * Q, FactoringAlgorithm::PrimalityCheck),
+                (P * Q, FactoringAlgorithm::PollardRho),
+                (log.rho_factors[0], FactoringAlgorithm::PrimalityCheck),
+                (
+                    P * Q / log.rho_factors[0],
+                    FactoringAlgorithm::PrimalityCheck
+                ),
+            ]
+        );
+        assert_eq!(log.started, log.finished);
+        assert_eq!(log.rho_attempts, vec![(P * Q, 1)]);
+        assert!(log.rho_iterations > 0);
+    }
+
+    #[derive(Default)]
+    struct CertificationLog {
+        started: Vec<u128>,
+        finished: Vec<(u128, bool)>,
+        n_minus_one: Vec<u128>,
+        added: Vec<u128>,
+    }
+
+    impl CertificationEventSubscriptor<u128> for CertificationLog {
+        fn certification_started(&mut self, n: &u128) {
+            self.started.push(*n);
+        }
+        fn certification_finished(&mut self, n: &u128, prime: bool, _elapsed: std::time::Duration) {
+            self.finished.push((*n, prime));
+        }
+        fn n_minus_one_factoring_started(&mut self, n: &u128) {
+            self.n_minus_one.push(*n);
+        }
+        fn element_added(&mut self, element: &LucasCertificateElement<u128>) {
+            self.added.push(element.n);
+        }
+    }
+
+    #[test]
+    fn certification_events() {
+        const N: u128 = 18_446_744_073_709_551_629;
+        let mut certificate = LucasCertificate::default();
+        let mut log = CertificationLog::default();
+        assert!(N.certified_prime_check_events(
+            PrimalityCertainty::Certified(&mut certificate),
+            &mut log
+        ));
+        assert_eq!(log.started[0], N);
+        assert_eq!(log.finished.last(), Some(&(N, true)));
+        assert_eq!(log.started.len(), log.finished.len());
+        assert_eq!(log.n_minus_one[0], N);
+        assert_eq!(log.added.last(), Some(&N));
+        let mut added = log.added;
+        added.sort_unstable();
+        assert_eq!(
+            added,
+            certificate.elements.iter().map(|x| x.n).collect::<Vec<_>>()
+        );
+
+        let mut log = CertificationLog::default();
+        assert!(!(N * 3).certified_prime_check_events(PrimalityCertainty::Guaranteed, &mut log));
+        assert_eq!(log.finished, vec![(N * 3, false)]);
+        assert!(log.added.is_empty());
+    }
+
+    mod budget {
+        use crate::{CancellationToken, Factoring, FactoringBudget};
+
+        const N: u128 = 4_611_686_018_427_387_847 * 4_611_686_018_427_387_817;
+
+        fn product(parts: &[u128]) -> u128 {
+            parts.iter().product()
+        }
+
+        #[test]
+        fn zero() {
+            let partial = 0u128.factor_with_budget(&FactoringBudget::default());
+            assert_eq!(partial.unknown, vec![0]);
+            assert!(partial.primes.is_empty());
+            assert!(!partial.is_complete());
+        }
+
+        #[test]
+        fn unlimited() {
+            const P: u128 = 4_294_967_279;
+            const Q: u128 = 4_294_967_291;
+            let partial = (P * Q * 12).factor_with_budget(&FactoringBudget::default());
+            assert!(partial.is_complete());
+            assert_eq!(partial.primes, vec![2, 2, 3, P, Q]);
+        }
+
+        #[test]
+        fn cancelled() {
+            let token = CancellationToken::new();
+            token.cancel();
+            let budget = FactoringBudget {
+                cancellation: Some(token),
+                ..FactoringBudget::default()
+            };
+            let partial = (N * 12).factor_with_budget(&budget);
+            assert_eq!(partial.primes, vec![2, 2, 3]);
+            assert_eq!(partial.composites, vec![N]);
+            assert!(partial.unknown.is_empty());
+        }
+
+        #[test]
+        fn iteration_limit() {
+            let budget = FactoringBudget {
+                max_iterations: Some(10),
+                ..FactoringBudget::default()
+            };
+            let partial = (N * 5).factor_with_budget(&budget);
+            assert!(!partial.is_complete());
+            assert_eq!(
+                product(&partial.primes) * product(&partial.composites) * product(&partial.unknown),
+                N * 5
+            );
+        }
+    }
 }
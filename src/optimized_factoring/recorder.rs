@@ -0,0 +1,280 @@
+use std::fmt::{Display, Write};
+
+use super::FactoringEventSubscriptor;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Primality of a node in a [`FactorTreeRecorder`]
+pub enum FactorStatus {
+    /// Number has been proven prime
+    Prime,
+    /// Number has been proven composite
+    Composite,
+    /// Primality of the number has not been determined
+    Unknown,
+}
+
+impl FactorStatus {
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Prime => "prime",
+            Self::Composite => "composite",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Number in the split tree recorded by a [`FactorTreeRecorder`]
+pub struct FactorTreeNode<T> {
+    /// The number itself
+    pub value: T,
+    /// Primality of the number
+    pub status: FactorStatus,
+    /// Indices of the parts this number was split into, see [`FactorTreeRecorder::nodes`]
+    pub children: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Observer recording how a number has been split into its factors
+///
+/// # Example
+/// ```
+/// use facto::{Factoring, FactorTreeRecorder};
+/// let mut tree = FactorTreeRecorder::new();
+/// 1_000_000_014_000_000_049u64.factor_events(&mut tree);
+/// assert_eq!(tree.to_text(), "1000000014000000049 (composite)\n  1000000007 (prime)\n  1000000007 (prime)\n");
+/// ```
+pub struct FactorTreeRecorder<T> {
+    nodes: Vec<FactorTreeNode<T>>,
+}
+
+impl<T> Default for FactorTreeRecorder<T> {
+    fn default() -> Self {
+        Self { nodes: vec![] }
+    }
+}
+
+impl<T> FactorTreeRecorder<T> {
+    #[must_use]
+    /// Create an empty recorder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    /// All recorded nodes, the root being the first
+    pub fn nodes(&self) -> &[FactorTreeNode<T>] {
+        &self.nodes
+    }
+
+    #[must_use]
+    /// The factored number, if any events have been recorded
+    pub fn root(&self) -> Option<&FactorTreeNode<T>> {
+        self.nodes.first()
+    }
+
+    /// Parts the number of `node` has been split into
+    pub fn children<'a>(
+        &'a self,
+        node: &'a FactorTreeNode<T>,
+    ) -> impl Iterator<Item = &'a FactorTreeNode<T>> + 'a {
+        node.children.iter().map(move |&x| &self.nodes[x])
+    }
+
+    /// Numbers which have not been split, from left to right
+    pub fn leaves(&self) -> impl Iterator<Item = &FactorTreeNode<T>> {
+        let mut stack: Vec<usize> = if self.nodes.is_empty() {
+            vec![]
+        } else {
+            vec![0]
+        };
+        std::iter::from_fn(move || {
+            while let Some(index) = stack.pop() {
+                let node = &self.nodes[index];
+                if node.children.is_empty() {
+                    return Some(node);
+                }
+                stack.extend(node.children.iter().rev());
+            }
+            None
+        })
+    }
+
+    fn push(&mut self, value: T, status: FactorStatus) -> usize {
+        self.nodes.push(FactorTreeNode {
+            value,
+            status,
+            children: vec![],
+        });
+        self.nodes.len() - 1
+    }
+}
+
+impl<T: PartialEq + Clone> FactorTreeRecorder<T> {
+    /// Find the leaf holding `n`, creating the root if nothing has been recorded yet
+    fn leaf(&mut self, n: &T) -> Option<usize> {
+        if self.nodes.is_empty() {
+            return Some(self.push(n.clone(), FactorStatus::Unknown));
+        }
+        self.nodes
+            .iter()
+            .position(|x| x.children.is_empty() && &x.value == n && x.status != FactorStatus::Prime)
+    }
+
+    fn set_status(&mut self, n: &T, status: FactorStatus) {
+        let index = self.nodes.iter().position(|x| {
+            x.children.is_empty() && &x.value == n && x.status == FactorStatus::Unknown
+        });
+        if let Some(index) = index.or_else(|| self.leaf(n)) {
+            self.nodes[index].status = status;
+        }
+    }
+}
+
+impl<T: Display> FactorTreeRecorder<T> {
+    #[must_use]
+    /// Render the tree as JSON
+    ///
+    /// Each node is an object with the keys `value`, `status` and `children`.
+    /// Values are rendered as strings, as they might exceed the precision of JSON numbers.
+    pub fn to_json(&self) -> String {
+        let mut result = String::new();
+        if !self.nodes.is_empty() {
+            self.write_json(0, &mut result);
+        }
+        result
+    }
+
+    fn write_json(&self, index: usize, out: &mut String) {
+        let node = &self.nodes[index];
+        write!(
+            out,
+            r#"{{"value":"{}","status":"{}","children":["#,
+            node.value,
+            node.status.name()
+        )
+        .unwrap();
+        for (i, &child) in node.children.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            self.write_json(child, out);
+        }
+        out.push_str("]}");
+    }
+
+    #[must_use]
+    /// Render the tree as indented text, one number per line
+    pub fn to_text(&self) -> String {
+        let mut result = String::new();
+        if !self.nodes.is_empty() {
+            self.write_text(0, 0, &mut result);
+        }
+        result
+    }
+
+    fn write_text(&self, index: usize, depth: usize, out: &mut String) {
+        let node = &self.nodes[index];
+        writeln!(
+            out,
+            "{:indent$}{} ({})",
+            "",
+            node.value,
+            node.status.name(),
+            indent = 2 * depth
+        )
+        .unwrap();
+        for &child in &node.children {
+            self.write_text(child, depth + 1, out);
+        }
+    }
+}
+
+impl<T: PartialEq + Clone> FactoringEventSubscriptor<T> for FactorTreeRecorder<T> {
+    fn factorized(&mut self, n: &T, primes: &[T], composites: &[T], unknown: &[T]) {
+        let Some(parent) = self.leaf(n) else {
+            return;
+        };
+        self.nodes[parent].status = FactorStatus::Composite;
+        let parts = [
+            (primes, FactorStatus::Prime),
+            (composites, FactorStatus::Composite),
+            (unknown, FactorStatus::Unknown),
+        ];
+        for (values, status) in parts {
+            for value in values {
+                let child = self.push(value.clone(), status);
+                self.nodes[parent].children.push(child);
+            }
+        }
+    }
+
+    fn is_prime(&mut self, n: &T) {
+        self.set_status(n, FactorStatus::Prime);
+    }
+
+    fn is_composite(&mut self, n: &T) {
+        self.set_status(n, FactorStatus::Composite);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FactorStatus, FactorTreeRecorder};
+    use crate::Factoring;
+
+    const P: u128 = 4_294_967_279;
+    const Q: u128 = 4_294_967_291;
+
+    #[test]
+    fn split_tree() {
+        let mut tree = FactorTreeRecorder::new();
+        assert_eq!((P * Q * 12).factor_events(&mut tree), vec![2, 2, 3, P, Q]);
+        let root = tree.root().unwrap();
+        assert_eq!(root.value, P * Q * 12);
+        assert_eq!(root.status, FactorStatus::Composite);
+        let children: Vec<_> = tree.children(root).map(|x| (x.value, x.status)).collect();
+        assert_eq!(
+            children,
+            vec![
+                (2, FactorStatus::Prime),
+                (2, FactorStatus::Prime),
+                (3, FactorStatus::Prime),
+                (P * Q, FactorStatus::Composite)
+            ]
+        );
+        let mut leaves: Vec<_> = tree.leaves().map(|x| (x.value, x.status)).collect();
+        leaves.sort_unstable_by_key(|x| x.0);
+        assert_eq!(
+            leaves,
+            [2, 2, 3, P, Q]
+                .iter()
+                .map(|&x| (x, FactorStatus::Prime))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn prime() {
+        let mut tree = FactorTreeRecorder::new();
+        P.factor_events(&mut tree);
+        assert_eq!(tree.nodes().len(), 1);
+        assert_eq!(tree.root().unwrap().status, FactorStatus::Prime);
+        assert_eq!(tree.to_text(), format!("{P} (prime)\n"));
+    }
+
+    #[test]
+    fn json() {
+        let mut tree = FactorTreeRecorder::new();
+        (P * Q * 2).factor_events(&mut tree);
+        let parsed: serde_json::Value = serde_json::from_str(&tree.to_json()).unwrap();
+        assert_eq!(parsed["value"], (P * Q * 2).to_string());
+        assert_eq!(parsed["status"], "composite");
+        assert_eq!(parsed["children"][0]["value"], "2");
+        assert_eq!(parsed["children"][1]["status"], "composite");
+        assert_eq!(
+            parsed["children"][1]["children"].as_array().unwrap().len(),
+            2
+        );
+    }
+}
@@ -0,0 +1,410 @@
+use std::convert::TryFrom;
+
+use rug::integer::Order;
+use rug::Integer;
+
+use super::{Factoring, FactoringBudget, Primality, TRIAL_THRESHHOLD};
+use crate::factoring::{
+    resumable_rug_rho, IterationLimit, PollardRhoProgress, PollardRhoStatistics, TrialDivision,
+};
+
+const MAGIC: &[u8] = b"facto-session";
+const VERSION: u8 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Factorization of a [`rug::Integer`] which can be run in steps, and be saved and restored in between
+///
+/// Composites fitting in a `u128` are factored in a single step, as they do not take long enough to require checkpoints.
+/// Like [`Factoring::factor_with_budget`], primality proofs of found factors are not interrupted.
+///
+/// # Example
+/// ```
+/// use facto::{FactoringBudget, FactoringSession, Integer};
+/// let m89 = (Integer::from(1) << 89u32) - 1u32;
+/// let n = m89 * 1_000_000_007u64 * 998_244_353u64;
+/// let mut session = FactoringSession::new(n.clone());
+/// let budget = FactoringBudget {
+///     max_iterations: Some(1000),
+///     ..FactoringBudget::default()
+/// };
+/// while !session.step(&budget) {
+///     // A preempted worker continues from the saved state
+///     session = FactoringSession::from_bytes(&session.to_bytes()).unwrap();
+/// }
+/// assert_eq!(session.primes().iter().product::<Integer>(), n);
+/// ```
+pub struct FactoringSession {
+    n: Integer,
+    started: bool,
+    primes: Vec<Integer>,
+    composites: Vec<Integer>,
+    increment: Integer,
+    progress: Option<PollardRhoProgress>,
+}
+
+impl FactoringSession {
+    #[must_use]
+    /// Create a session factoring `n`, no work is done until [`FactoringSession::step`] is called
+    ///
+    /// # Panics
+    /// If `n` is not positive
+    pub fn new(n: Integer) -> Self {
+        assert!(n > 0, "Can only factor positive numbers");
+        Self {
+            n,
+            started: false,
+            primes: vec![],
+            composites: vec![],
+            increment: Integer::from(1),
+            progress: None,
+        }
+    }
+
+    #[must_use]
+    /// Number being factored
+    pub const fn n(&self) -> &Integer {
+        &self.n
+    }
+
+    #[must_use]
+    /// Factors proven to be prime so far, sorted once the factorization is finished
+    pub fn primes(&self) -> &[Integer] {
+        &self.primes
+    }
+
+    #[must_use]
+    /// Factors proven to be composite, which still have to be split
+    ///
+    /// Pollard's rho is applied to the last one
+    pub fn composites(&self) -> &[Integer] {
+        &self.composites
+    }
+
+    #[must_use]
+    /// Increment of the series used by pollard's rho on the last composite
+    pub const fn increment(&self) -> &Integer {
+        &self.increment
+    }
+
+    #[must_use]
+    /// State of the interrupted run of pollard's rho on the last composite
+    pub const fn rho_progress(&self) -> Option<&PollardRhoProgress> {
+        self.progress.as_ref()
+    }
+
+    #[must_use]
+    /// Whether the factorization finished, i.e. [`FactoringSession::primes`] is the complete prime factorization
+    pub const fn is_finished(&self) -> bool {
+        self.started && self.composites.is_empty()
+    }
+
+    /// Continue the factorization until it is finished or `budget` is exhausted
+    ///
+    /// # Returns
+    /// Whether the factorization finished
+    pub fn step(&mut self, budget: &FactoringBudget) -> bool {
+        if !self.started {
+            self.started = true;
+            let (mut factors, exhaustive) = self
+                .n
+                .clone()
+                .trial_division(&Integer::from(TRIAL_THRESHHOLD));
+            let rest = if exhaustive { None } else { factors.pop() };
+            self.primes.extend(factors);
+            if let Some(rest) = rest {
+                self.push_part(rest);
+            }
+        }
+        let mut limit = budget.tracker();
+        while let Some(composite) = self.composites.pop() {
+            if limit.exhausted() {
+                self.composites.push(composite);
+                return false;
+            }
+            let two = Integer::from(2);
+            let (factor, _) = resumable_rug_rho(
+                &composite,
+                &two,
+                &self.increment,
+                &mut self.progress,
+                &mut limit,
+            );
+            if let Some(f) = factor {
+                self.increment = Integer::from(1);
+                let other_factor = composite / &f;
+                self.push_part(f);
+                self.push_part(other_factor);
+            } else {
+                if self.progress.is_none() {
+                    self.increment += 1;
+                }
+                self.composites.push(composite);
+            }
+        }
+        self.primes.sort_unstable();
+        true
+    }
+
+    fn push_part(&mut self, part: Integer) {
+        if let Some(x) = part.to_u128() {
+            self.primes
+                .extend(x.factor().into_iter().map(Integer::from));
+        } else if part.clone().is_prime() {
+            self.primes.push(part);
+        } else {
+            self.composites.push(part);
+        }
+    }
+
+    #[must_use]
+    /// Serialize the state of the session, to be restored by [`FactoringSession::from_bytes`]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut result = MAGIC.to_vec();
+        result.push(VERSION);
+        result.push(u8::from(self.started));
+        write_integer(&mut result, &self.n);
+        write_integers(&mut result, &self.primes);
+        write_integers(&mut result, &self.composites);
+        write_integer(&mut result, &self.increment);
+        if let Some(p) = &self.progress {
+            result.push(1);
+            for x in [
+                &p.tortoise,
+                &p.hare,
+                &p.power,
+                &p.count,
+                &p.accum,
+                &p.last_tortoise,
+                &p.last_hare,
+            ] {
+                write_integer(&mut result, x);
+            }
+            result.extend(p.statistics.iterations.to_le_bytes());
+            result.extend(p.statistics.gcds.to_le_bytes());
+        } else {
+            result.push(0);
+        }
+        result
+    }
+
+    #[must_use]
+    /// Restore a session from the output of [`FactoringSession::to_bytes`]
+    ///
+    /// # Returns
+    /// `None` if `bytes` is not a valid serialized session
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut reader = Reader(bytes);
+        if reader.take(MAGIC.len())? != MAGIC || reader.byte()? != VERSION {
+            return None;
+        }
+        let started = reader.flag()?;
+        let n = reader.integer()?;
+        let primes = reader.integers()?;
+        let composites = reader.integers()?;
+        let increment = reader.integer()?;
+        let progress = if reader.flag()? {
+            Some(PollardRhoProgress {
+                tortoise: reader.integer()?,
+                hare: reader.integer()?,
+                power: reader.integer()?,
+                count: reader.integer()?,
+                accum: reader.integer()?,
+                last_tortoise: reader.integer()?,
+                last_hare: reader.integer()?,
+                statistics: PollardRhoStatistics {
+                    iterations: reader.u64()?,
+                    gcds: reader.u64()?,
+                },
+            })
+        } else {
+            None
+        };
+        if !reader.0.is_empty() || n <= 0 || increment < 1 {
+            return None;
+        }
+        if primes.iter().any(|p| *p < 2)
+            || composites.iter().any(|c| c.is_even() || *c <= u128::MAX)
+        {
+            return None;
+        }
+        if let Some(p) = &progress {
+            // Pollard's rho runs on the last composite, and its state has to be reduced modulo it
+            let composite = composites.last()?;
+            let reduced = |x: &Integer| *x >= 0 && x < composite;
+            let valid = p.power >= 1
+                && p.power.is_power_of_two()
+                && p.count >= 0
+                && p.count < p.power
+                && reduced(&increment)
+                && [
+                    &p.tortoise,
+                    &p.hare,
+                    &p.accum,
+                    &p.last_tortoise,
+                    &p.last_hare,
+                ]
+                .iter()
+                .all(|x| reduced(x));
+            if !valid {
+                return None;
+            }
+        }
+        Some(Self {
+            n,
+            started,
+            primes,
+            composites,
+            increment,
+            progress,
+        })
+    }
+}
+
+fn write_integer(out: &mut Vec<u8>, x: &Integer) {
+    let digits = x.to_digits::<u8>(Order::Lsf);
+    out.extend(u64::try_from(digits.len()).unwrap().to_le_bytes());
+    out.extend(digits);
+}
+
+fn write_integers(out: &mut Vec<u8>, xs: &[Integer]) {
+    out.extend(u64::try_from(xs.len()).unwrap().to_le_bytes());
+    for x in xs {
+        write_integer(out, x);
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    const fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.0.len() < len {
+            return None;
+        }
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Some(head)
+    }
+
+    fn byte(&mut self) -> Option<u8> {
+        self.take(1).map(|x| x[0])
+    }
+
+    fn flag(&mut self) -> Option<bool> {
+        match self.byte()? {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        let mut buffer = [0; 8];
+        buffer.copy_from_slice(self.take(8)?);
+        Some(u64::from_le_bytes(buffer))
+    }
+
+    fn integer(&mut self) -> Option<Integer> {
+        let len = usize::try_from(self.u64()?).ok()?;
+        Some(Integer::from_digits(self.take(len)?, Order::Lsf))
+    }
+
+    fn integers(&mut self) -> Option<Vec<Integer>> {
+        let len = self.u64()?;
+        // Do not trust the length for the allocation, every element takes at least 8 bytes
+        let mut result = Vec::with_capacity(usize::try_from(len).ok()?.min(self.0.len() / 8));
+        for _ in 0..len {
+            result.push(self.integer()?);
+        }
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FactoringSession;
+    use crate::{Factoring, FactoringBudget, Integer};
+
+    fn composite() -> Integer {
+        // Mersenne prime 2^89 - 1 keeps the product above u128
+        let m89 = (Integer::from(1) << 89u32) - 1u32;
+        m89 * 1_000_000_007u64 * 998_244_353u64 * 12u32
+    }
+
+    fn budget(iterations: u64) -> FactoringBudget {
+        FactoringBudget {
+            max_iterations: Some(iterations),
+            ..FactoringBudget::default()
+        }
+    }
+
+    #[test]
+    fn stepped() {
+        let n = composite();
+        let mut session = FactoringSession::new(n.clone());
+        let mut steps = 0;
+        while !session.step(&budget(100)) {
+            steps += 1;
+            assert!(!session.is_finished());
+            assert!(session.rho_progress().is_some());
+            assert!(!session.composites().is_empty());
+        }
+        assert!(steps > 1);
+        assert!(session.is_finished());
+        assert_eq!(session.primes(), n.factor().as_slice());
+    }
+
+    #[test]
+    fn checkpoint() {
+        let n = composite();
+        let mut session = FactoringSession::new(n.clone());
+        assert!(!session.step(&budget(500)));
+        let bytes = session.to_bytes();
+        let restored = FactoringSession::from_bytes(&bytes).unwrap();
+        assert_eq!(restored, session);
+
+        let mut uninterrupted = session.clone();
+        assert!(uninterrupted.step(&FactoringBudget::default()));
+        let mut resumed = restored;
+        while !resumed.step(&budget(500)) {
+            resumed = FactoringSession::from_bytes(&resumed.to_bytes()).unwrap();
+        }
+        assert_eq!(resumed, uninterrupted);
+        assert_eq!(resumed.primes(), n.factor().as_slice());
+    }
+
+    #[test]
+    fn invalid_bytes() {
+        let bytes = FactoringSession::new(Integer::from(15)).to_bytes();
+        assert!(FactoringSession::from_bytes(&bytes).is_some());
+        assert!(FactoringSession::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+        assert!(FactoringSession::from_bytes(&[bytes.as_slice(), &[0]].concat()).is_none());
+        assert!(FactoringSession::from_bytes(b"facto").is_none());
+    }
+
+    #[test]
+    fn corrupted_bytes() {
+        let mut session = FactoringSession::new(composite());
+        assert!(!session.step(&budget(500)));
+        let bytes = session.to_bytes();
+        // Corrupting the state of pollard's rho, which follows the flag of the saved progress
+        let mut without_progress = session.clone();
+        without_progress.progress = None;
+        for i in without_progress.to_bytes().len()..bytes.len() {
+            for bit in 0..8 {
+                let mut corrupted = bytes.clone();
+                corrupted[i] ^= 1 << bit;
+                // Accepted checkpoints have to be safe to continue
+                if let Some(mut restored) = FactoringSession::from_bytes(&corrupted) {
+                    restored.step(&budget(100));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn small() {
+        let mut session = FactoringSession::new(Integer::from(60));
+        assert!(session.step(&budget(0)));
+        assert_eq!(session.primes(), [2, 2, 3, 5]);
+    }
+}
@@ -1,4 +1,5 @@
 use redc::Redc;
+use rug::Integer;
 
 #[allow(clippy::module_name_repetitions)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -27,9 +28,86 @@ pub trait LucasPrimality: Sized {
         n_minus_1_unique_prime_factors: &[Self],
         base: Self,
     ) -> LucasPrimalityResult;
+
+    /// Check `self` for primality using pocklington's criterion, needing only a partial factorization of `self` - 1
+    ///
+    /// <https://en.wikipedia.org/wiki/Pocklington_primality_test>
+    /// # Arguments
+    /// * `self`: Number to be checked for primality
+    /// * `n_minus_1_unique_prime_factors`: Prime factors of `self` - 1, whose full powers dividing `self` - 1 multiply to more than the square root of `self`
+    /// * `base`: Base used to test `self` for all factors at once
+    ///
+    /// # Example
+    /// ```
+    /// use facto::primality::{LucasPrimality, LucasPrimalityResult};
+    /// // 1019 - 1 = 2 * 509
+    /// assert_eq!(1019u64.pocklington_test(&[509], 2), LucasPrimalityResult::Prime);
+    /// assert_eq!(1019u64.pocklington_test(&[2], 2), LucasPrimalityResult::Unknown);
+    /// ```
+    fn pocklington_test(
+        self,
+        n_minus_1_unique_prime_factors: &[Self],
+        base: Self,
+    ) -> LucasPrimalityResult;
+}
+
+/// Pocklington's criterion for any integer type, see [`LucasPrimality::pocklington_test`]
+fn pocklington_test(n: &Integer, factors: &[Integer], base: &Integer) -> LucasPrimalityResult {
+    if *n <= 2 {
+        return if *n == 2 {
+            LucasPrimalityResult::Prime
+        } else {
+            LucasPrimalityResult::Composite
+        };
+    }
+    let n_minus_one = Integer::from(n - 1);
+    let power = |exponent: &Integer| {
+        Integer::from(
+            base.pow_mod_ref(exponent, n)
+                .unwrap_or_else(|| unreachable!()),
+        )
+    };
+    if power(&n_minus_one) != 1 {
+        return LucasPrimalityResult::Composite;
+    }
+    // Dividing all factors out of the same rest counts repeated factors only once
+    let mut rest = n_minus_one.clone();
+    for factor in factors {
+        if *factor < 2 || !n_minus_one.is_divisible(factor) {
+            return LucasPrimalityResult::Unknown;
+        }
+        while rest.is_divisible(factor) {
+            rest /= factor;
+        }
+        let gcd = (power(&Integer::from(&n_minus_one / factor)) - 1u32).gcd(n);
+        if gcd == *n {
+            return LucasPrimalityResult::Unknown;
+        }
+        if gcd != 1 {
+            return LucasPrimalityResult::Composite;
+        }
+    }
+    let factored = n_minus_one / rest;
+    if factored.square() > *n {
+        LucasPrimalityResult::Prime
+    } else {
+        LucasPrimalityResult::Unknown
+    }
 }
 
 impl LucasPrimality for u64 {
+    fn pocklington_test(
+        self,
+        n_minus_1_unique_prime_factors: &[Self],
+        base: Self,
+    ) -> LucasPrimalityResult {
+        let factors: Vec<Integer> = n_minus_1_unique_prime_factors
+            .iter()
+            .map(|&factor| Integer::from(factor))
+            .collect();
+        pocklington_test(&Integer::from(self), &factors, &Integer::from(base))
+    }
+
     fn lucas_primality_test(
         self,
         n_minus_1_unique_prime_factors: &[Self],
@@ -52,6 +130,18 @@ impl LucasPrimality for u64 {
 }
 
 impl LucasPrimality for u128 {
+    fn pocklington_test(
+        self,
+        n_minus_1_unique_prime_factors: &[Self],
+        base: Self,
+    ) -> LucasPrimalityResult {
+        let factors: Vec<Integer> = n_minus_1_unique_prime_factors
+            .iter()
+            .map(|&factor| Integer::from(factor))
+            .collect();
+        pocklington_test(&Integer::from(self), &factors, &Integer::from(base))
+    }
+
     fn lucas_primality_test(
         self,
         n_minus_1_unique_prime_factors: &[Self],
@@ -74,6 +164,14 @@ impl LucasPrimality for u128 {
 }
 
 impl LucasPrimality for rug::Integer {
+    fn pocklington_test(
+        self,
+        n_minus_1_unique_prime_factors: &[Self],
+        base: Self,
+    ) -> LucasPrimalityResult {
+        pocklington_test(&self, n_minus_1_unique_prime_factors, &base)
+    }
+
     fn lucas_primality_test(
         self,
         n_minus_1_unique_prime_factors: &[Self],
@@ -98,6 +196,7 @@ impl LucasPrimality for rug::Integer {
 #[cfg(test)]
 mod tests {
     use super::{LucasPrimality, LucasPrimalityResult};
+    use std::convert::TryFrom;
     /// # Panics
     /// Should never happen, happens when primality can not be proved or disproved
     pub fn exhaustive_lucas_primality_test<T>(
@@ -143,4 +242,51 @@ mod tests {
         )
         .is_some());
     }
+
+    #[test]
+    fn test_pocklington() {
+        // 1019 - 1 = 2 * 509, 2^2 - 1 is coprime to 1019
+        assert_eq!(
+            1019u64.pocklington_test(&[509], 2),
+            LucasPrimalityResult::Prime
+        );
+        // 509 * 1019 fails the fermat test
+        assert_eq!(
+            518_671u64.pocklington_test(&[509], 2),
+            LucasPrimalityResult::Composite
+        );
+        // 4^((1019 - 1) / 2) is 1 modulo 1019, as 4 is a quadratic residue
+        assert_eq!(
+            1019u64.pocklington_test(&[2, 509], 4),
+            LucasPrimalityResult::Unknown
+        );
+        assert_eq!(
+            1019u64.pocklington_test(&[3], 2),
+            LucasPrimalityResult::Unknown
+        );
+        // Repeated factors count once, 15 - 1 = 2 * 7 is only factored up to 2
+        assert_eq!(
+            15u64.pocklington_test(&[2, 2], 14),
+            LucasPrimalityResult::Unknown
+        );
+        assert_eq!(
+            85u64.pocklington_test(&[2, 2], 13),
+            LucasPrimalityResult::Unknown
+        );
+        // 2^64 + 13 - 1 = 2^2 * 7 * 658812288346769701
+        let n = (rug::Integer::from(1) << 64u32) + 13u32;
+        assert_eq!(
+            n.clone().pocklington_test(
+                &[rug::Integer::from(658_812_288_346_769_701u64)],
+                rug::Integer::from(2)
+            ),
+            LucasPrimalityResult::Prime
+        );
+        assert_eq!(
+            u128::try_from(n)
+                .unwrap()
+                .pocklington_test(&[658_812_288_346_769_701], 2),
+            LucasPrimalityResult::Prime
+        );
+    }
 }
@@ -0,0 +1,429 @@
+use std::convert::TryFrom;
+use std::fmt::Debug;
+use std::ops::Range;
+
+use rug::Integer;
+
+use super::segmented::small_primes;
+use crate::util::inverse_mod;
+use crate::{LucasCertificate, Primality};
+
+/// Offsets of twin primes
+pub const TWIN_PRIMES: &[u64] = &[0, 2];
+/// Offsets of the two kinds of prime triplets
+pub const PRIME_TRIPLETS: [&[u64]; 2] = [&[0, 2, 6], &[0, 4, 6]];
+/// Offsets of prime quadruplets
+pub const PRIME_QUADRUPLETS: &[u64] = &[0, 2, 6, 8];
+
+/// Exclusive bound of the primes used for sieving
+const SIEVE_LIMIT: u64 = 1 << 16;
+
+/// Starting points checked at once
+const SEGMENT_LENGTH: u64 = 1 << 16;
+
+/// Integers usable as starting points of searches over many numbers, implemented for `u64` and [`Integer`]
+pub trait SearchInteger: Primality + Clone + Ord + Debug {
+    /// Remainder of the euclidean division by the small number `m`
+    fn rem_small(&self, m: u64) -> u64;
+    /// The number plus `k`, if it fits into the type
+    fn checked_add_small(&self, k: u64) -> Option<Self>;
+    /// The number `a * self + b`, if it is positive and fits into the type
+    fn linear(&self, a: u64, b: i64) -> Option<Self>;
+    /// The number as `u64`, if it fits
+    fn to_small(&self) -> Option<u64>;
+}
+
+impl SearchInteger for u64 {
+    fn rem_small(&self, m: u64) -> u64 {
+        self % m
+    }
+
+    fn checked_add_small(&self, k: u64) -> Option<Self> {
+        self.checked_add(k)
+    }
+
+    fn linear(&self, a: u64, b: i64) -> Option<Self> {
+        let product = self.checked_mul(a)?;
+        if b < 0 {
+            product.checked_sub(b.unsigned_abs())
+        } else {
+            product.checked_add(b.unsigned_abs())
+        }
+        .filter(|x| *x > 0)
+    }
+
+    fn to_small(&self) -> Option<u64> {
+        Some(*self)
+    }
+}
+
+impl SearchInteger for Integer {
+    fn rem_small(&self, m: u64) -> u64 {
+        u64::from(self.mod_u(u32::try_from(m).expect("Modulus exceeds u32")))
+    }
+
+    fn checked_add_small(&self, k: u64) -> Option<Self> {
+        Some(Self::from(self + k))
+    }
+
+    fn linear(&self, a: u64, b: i64) -> Option<Self> {
+        Some(Self::from(self * a) + b).filter(|x| *x > 0)
+    }
+
+    fn to_small(&self) -> Option<u64> {
+        self.to_u64()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Kind of a cunningham chain
+pub enum CunninghamKind {
+    /// Chains p, 2p + 1, 4p + 3, …, each prime being a sophie germain prime of the next
+    First,
+    /// Chains p, 2p - 1, 4p - 3, …
+    Second,
+}
+
+#[derive(Debug, Clone)]
+/// Primes found by a [`ConstellationSearch`]
+pub struct PrimeConstellation<T> {
+    /// The primes, in the order of the pattern
+    pub primes: Vec<T>,
+    /// Certificates of the primes, if requested by [`ConstellationSearch::with_certificates`]
+    pub certificates: Option<Vec<LucasCertificate<T>>>,
+}
+
+#[derive(Debug, Clone)]
+/// Iterator over the starting points n of a range, for which the linear forms a * n + b of a pattern are all prime
+///
+/// Segments of starting points are sieved by the primes below 2^16, removing those where any form has a small factor.
+/// The survivors are checked using [`Primality::is_prime`].
+///
+/// # Example
+/// ```
+/// use facto::sieve::{ConstellationSearch, CunninghamKind, PRIME_QUADRUPLETS};
+/// let quadruplets: Vec<Vec<u64>> = ConstellationSearch::tuples(0..1000, PRIME_QUADRUPLETS)
+///     .map(|c| c.primes)
+///     .collect();
+/// assert_eq!(quadruplets, [[5, 7, 11, 13], [11, 13, 17, 19], [101, 103, 107, 109], [191, 193, 197, 199], [821, 823, 827, 829]]);
+/// let chain = ConstellationSearch::cunningham_chains(0..100, CunninghamKind::First, 5)
+///     .next()
+///     .unwrap();
+/// assert_eq!(chain.primes, [2, 5, 11, 23, 47]);
+/// ```
+pub struct ConstellationSearch<T> {
+    /// Pairs (a, b) of the linear forms a * n + b
+    forms: Vec<(u64, i64)>,
+    /// Sieving primes with the residues of starting points making any form divisible by them
+    residues: Vec<(u64, Vec<u64>)>,
+    /// Ascending starting points at which a form equals a sieving prime, which are checked without sieving
+    exceptions: Vec<u64>,
+    next: Option<T>,
+    end: T,
+    current_start: T,
+    candidates: std::vec::IntoIter<u64>,
+    certify: bool,
+}
+
+impl<T: SearchInteger> ConstellationSearch<T> {
+    /// Search for starting points n in `range` with all the linear forms a * n + b given by the pairs in `forms` prime
+    ///
+    /// # Panics
+    /// If `forms` is empty, or any a is zero
+    pub fn linear_forms(range: Range<T>, forms: Vec<(u64, i64)>) -> Self {
+        assert!(!forms.is_empty(), "Search needs at least one form");
+        assert!(
+            forms.iter().all(|(a, _)| *a > 0),
+            "Forms need a positive factor"
+        );
+        let residues = [2, 3, 5]
+            .iter()
+            .copied()
+            .chain(small_primes(SIEVE_LIMIT))
+            .map(|p| {
+                let mut bad: Vec<u64> = forms
+                    .iter()
+                    .filter(|(a, _)| a % p != 0)
+                    .map(|&(a, b)| {
+                        let b = b.rem_euclid(i64::try_from(p).unwrap_or_else(|_| unreachable!()));
+                        // a * n + b ≡ 0 modulo p
+                        (p - b.unsigned_abs()) % p * inverse_mod(a, p) % p
+                    })
+                    .collect();
+                bad.sort_unstable();
+                bad.dedup();
+                (p, bad)
+            })
+            .collect::<Vec<_>>();
+        let mut exceptions: Vec<u64> = residues
+            .iter()
+            .flat_map(|(p, _)| {
+                forms.iter().filter_map(move |&(a, b)| {
+                    // a * n + b = p
+                    let difference = i128::from(*p) - i128::from(b);
+                    let a = i128::from(a);
+                    (difference >= 0 && difference % a == 0)
+                        .then(|| u64::try_from(difference / a).unwrap_or_else(|_| unreachable!()))
+                })
+            })
+            .collect();
+        exceptions.sort_unstable();
+        exceptions.dedup();
+        Self {
+            forms,
+            residues,
+            exceptions,
+            current_start: range.start.clone(),
+            next: Some(range.start),
+            end: range.end,
+            candidates: Vec::new().into_iter(),
+            certify: false,
+        }
+    }
+
+    /// Search for starting points n in `range` with n + o prime for all `offsets` o, like [`TWIN_PRIMES`]
+    ///
+    /// # Panics
+    /// If the offsets do not start at zero, are not ascending or the pattern is not admissible,
+    /// meaning that for some prime p the offsets cover all residues modulo p
+    pub fn tuples(range: Range<T>, offsets: &[u64]) -> Self {
+        assert!(
+            offsets.first() == Some(&0) && offsets.windows(2).all(|w| w[0] < w[1]),
+            "Offsets need to start at 0 and ascend"
+        );
+        let length = offsets.len() as u64;
+        for p in (2..=length).filter(|p| (2..*p).all(|d| p % d != 0)) {
+            let mut covered: Vec<u64> = offsets.iter().map(|o| o % p).collect();
+            covered.sort_unstable();
+            covered.dedup();
+            assert!(
+                covered.len() as u64 != p,
+                "Pattern is not admissible modulo {}",
+                p
+            );
+        }
+        let forms = offsets
+            .iter()
+            .map(|&o| (1, i64::try_from(o).expect("Offset exceeds i64")))
+            .collect();
+        Self::linear_forms(range, forms)
+    }
+
+    /// Search for cunningham chains of `length` primes, starting in `range`
+    ///
+    /// The chains found may be part of longer chains.
+    ///
+    /// # Panics
+    /// If `length` is zero or exceeds 62
+    pub fn cunningham_chains(range: Range<T>, kind: CunninghamKind, length: u32) -> Self {
+        assert!(
+            (1..=62).contains(&length),
+            "Chain length needs to be in 1..=62"
+        );
+        let forms = (0..length)
+            .map(|i| {
+                let b = (1i64 << i) - 1;
+                match kind {
+                    CunninghamKind::First => (1 << i, b),
+                    CunninghamKind::Second => (1 << i, -b),
+                }
+            })
+            .collect();
+        Self::linear_forms(range, forms)
+    }
+
+    #[must_use]
+    /// Generate lucas certificates for the primes found, using [`Primality::generate_lucas_certificate`]
+    pub const fn with_certificates(mut self) -> Self {
+        self.certify = true;
+        self
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn sieve_next_segment(&mut self, start: T) {
+        let mut keep = vec![true; SEGMENT_LENGTH as usize];
+        for (p, bad) in &self.residues {
+            let r = start.rem_small(*p);
+            for b in bad {
+                let mut i = (b + p - r) % p;
+                while i < SEGMENT_LENGTH {
+                    keep[i as usize] = false;
+                    i += p;
+                }
+            }
+        }
+        if let Some(s) = start.to_small() {
+            let end = s.saturating_add(SEGMENT_LENGTH);
+            let first = self.exceptions.partition_point(|e| *e < s);
+            for e in self.exceptions[first..].iter().take_while(|e| **e < end) {
+                keep[(e - s) as usize] = true;
+            }
+        }
+        self.candidates = (0..SEGMENT_LENGTH)
+            .filter(|&i| keep[i as usize])
+            .collect::<Vec<_>>()
+            .into_iter();
+        self.next = start
+            .checked_add_small(SEGMENT_LENGTH)
+            .filter(|next| *next < self.end);
+        self.current_start = start;
+    }
+
+    /// The values of the forms at `n`, if all are prime
+    fn confirm(&self, n: &T) -> Option<Vec<T>> {
+        self.forms
+            .iter()
+            .map(|&(a, b)| n.linear(a, b).filter(|x| x.clone().is_prime()))
+            .collect()
+    }
+}
+
+impl<T: SearchInteger> Iterator for ConstellationSearch<T> {
+    type Item = PrimeConstellation<T>;
+
+    fn next(&mut self) -> Option<PrimeConstellation<T>> {
+        loop {
+            while let Some(offset) = self.candidates.next() {
+                let n = self.current_start.checked_add_small(offset)?;
+                if n >= self.end {
+                    self.candidates = Vec::new().into_iter();
+                    break;
+                }
+                if let Some(primes) = self.confirm(&n) {
+                    let certificates = self.certify.then(|| {
+                        primes
+                            .iter()
+                            .map(|p| {
+                                p.clone()
+                                    .generate_lucas_certificate()
+                                    .unwrap_or_else(|| unreachable!())
+                            })
+                            .collect()
+                    });
+                    return Some(PrimeConstellation {
+                        primes,
+                        certificates,
+                    });
+                }
+            }
+            let start = self.next.take()?;
+            if start >= self.end {
+                return None;
+            }
+            self.sieve_next_segment(start);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ConstellationSearch, CunninghamKind, PRIME_QUADRUPLETS, PRIME_TRIPLETS, TWIN_PRIMES,
+    };
+    use crate::{Integer, LucasCertificateElement, Primality};
+    use std::convert::TryFrom;
+
+    fn naive(range: std::ops::Range<u64>, forms: &[(u64, i64)]) -> Vec<Vec<u64>> {
+        range
+            .filter_map(|n| {
+                forms
+                    .iter()
+                    .map(|&(a, b)| {
+                        u64::try_from(i128::from(a) * i128::from(n) + i128::from(b))
+                            .ok()
+                            .filter(|x| x.is_prime())
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn tuples() {
+        let patterns = [
+            TWIN_PRIMES,
+            PRIME_TRIPLETS[0],
+            PRIME_TRIPLETS[1],
+            PRIME_QUADRUPLETS,
+            &[0, 4, 6, 10, 12, 16],
+        ];
+        for pattern in patterns {
+            let forms: Vec<(u64, i64)> = pattern
+                .iter()
+                .map(|&o| (1, i64::try_from(o).unwrap()))
+                .collect();
+            for range in [0..200_000, (1 << 40)..(1 << 40) + 200_000] {
+                let expected = naive(range.clone(), &forms);
+                let found: Vec<Vec<u64>> = ConstellationSearch::tuples(range.clone(), pattern)
+                    .map(|c| c.primes)
+                    .collect();
+                assert_eq!(found, expected, "{pattern:?}");
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "not admissible")]
+    fn inadmissible() {
+        let _ = ConstellationSearch::tuples(0..100u64, &[0, 2, 4]);
+    }
+
+    #[test]
+    fn sieving_primes_as_values() {
+        let found: Vec<Vec<u64>> =
+            ConstellationSearch::linear_forms(100_000u64..100_010, vec![(1, -99_999)])
+                .map(|c| c.primes)
+                .collect();
+        assert_eq!(found, [[2], [3], [5], [7]]);
+        let forms = vec![(3, -299_995), (1, 2)];
+        let found: Vec<Vec<u64>> =
+            ConstellationSearch::linear_forms(99_990u64..100_010, forms.clone())
+                .map(|c| c.primes)
+                .collect();
+        assert_eq!(found, naive(99_990..100_010, &forms));
+    }
+
+    #[test]
+    fn cunningham() {
+        for kind in [CunninghamKind::First, CunninghamKind::Second] {
+            for length in 1..6 {
+                let search = ConstellationSearch::cunningham_chains(0..100_000u64, kind, length);
+                let forms: Vec<(u64, i64)> = search.forms.clone();
+                let found: Vec<Vec<u64>> = search.map(|c| c.primes).collect();
+                assert_eq!(found, naive(0..100_000, &forms), "{kind:?} {length}");
+            }
+        }
+        // Smallest first kind chain of length 7 starts at 1122659
+        let chain = ConstellationSearch::cunningham_chains(
+            1_000_000..2_000_000u64,
+            CunninghamKind::First,
+            7,
+        )
+        .next()
+        .unwrap();
+        assert_eq!(chain.primes[0], 1_122_659);
+    }
+
+    #[test]
+    fn large_numbers() {
+        let start = Integer::from(1) << 80u32;
+        let end = Integer::from(&start + 1_000_000u32);
+        let twins: Vec<_> = ConstellationSearch::tuples(start..end, TWIN_PRIMES)
+            .with_certificates()
+            .take(2)
+            .collect();
+        assert_eq!(twins.len(), 2);
+        for twin in twins {
+            assert_eq!(Integer::from(&twin.primes[1] - &twin.primes[0]), 2);
+            let certificates = twin.certificates.unwrap();
+            for (p, certificate) in twin.primes.iter().zip(certificates) {
+                assert!(p.clone().is_prime());
+                assert_eq!(certificate.get_max().unwrap().n, *p);
+                assert!(certificate
+                    .elements
+                    .iter()
+                    .all(LucasCertificateElement::verify));
+            }
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use std::ops::{Mul, Sub};
+
+use super::{primes, primes_in};
+use crate::util::NumUtil;
+use crate::Primality;
+
+/// Sums of `weight` over the primes up to x, by the method of `Lucy_Hedgehog`
+///
+/// Keeps the sums for all values ⌊x / k⌋, starting with the sums over all numbers from 2 as given by `initial`,
+/// then removing the multiples of each prime p ≤ √x, which are taken from the sieve.
+/// Needs O(x^(3/4)) time and O(√x) space.
+#[allow(clippy::cast_possible_truncation)]
+fn lucy<T>(x: u64, initial: impl Fn(u64) -> T, weight: impl Fn(u64) -> T) -> T
+where
+    T: Copy + Sub<Output = T> + Mul<Output = T>,
+{
+    let r = x.integer_square_root();
+    // small[v] is the sum up to v, large[k] the sum up to x / k
+    let mut small: Vec<T> = (0..=r).map(&initial).collect();
+    let mut large: Vec<T> = (0..=r).map(|k| initial(x / k.max(1))).collect();
+    for p in primes_in(2..r + 1) {
+        let below = small[p as usize - 1];
+        let square = p * p;
+        let w = weight(p);
+        for k in 1..=r.min(x / square) {
+            let d = k * p;
+            let quotient = if d <= r {
+                large[d as usize]
+            } else {
+                small[(x / d) as usize]
+            };
+            large[k as usize] = large[k as usize] - w * (quotient - below);
+        }
+        for v in (square..=r).rev() {
+            small[v as usize] = small[v as usize] - w * (small[(v / p) as usize] - below);
+        }
+    }
+    large[1]
+}
+
+#[must_use]
+/// Number of primes up to and including `x`, π(x)
+///
+/// Uses the method of `Lucy_Hedgehog`, taking O(x^(3/4)) time and O(√x) space.
+///
+/// # Example
+/// ```
+/// use facto::sieve::prime_count;
+/// assert_eq!(prime_count(100), 25);
+/// assert_eq!(prime_count(1_000_000_000), 50_847_534);
+/// ```
+pub fn prime_count(x: u64) -> u64 {
+    if x < 2 {
+        return 0;
+    }
+    lucy(x, |v| v.max(1) - 1, |_| 1)
+}
+
+#[must_use]
+/// Sum of the primes up to and including `x`
+///
+/// Uses the method of `Lucy_Hedgehog` like [`prime_count`].
+///
+/// # Example
+/// ```
+/// use facto::sieve::prime_sum;
+/// assert_eq!(prime_sum(10), 2 + 3 + 5 + 7);
+/// assert_eq!(prime_sum(2_000_000), 142_913_828_922);
+/// ```
+pub fn prime_sum(x: u64) -> u128 {
+    if x < 2 {
+        return 0;
+    }
+    lucy(
+        x,
+        |v| {
+            let v = u128::from(v);
+            (v * (v + 1) / 2).max(1) - 1
+        },
+        u128::from,
+    )
+}
+
+#[must_use]
+/// The `n`th prime, counting from `nth_prime(1) = 2`
+///
+/// Counts the primes up to a lower bound of the `n`th prime using [`prime_count`], and sieves from there.
+/// The result is checked using [`Primality::is_prime`].
+///
+/// # Returns
+/// `None` if `n` is zero, or the `n`th prime does not fit into a `u64`
+///
+/// # Panics
+/// If the sieve yields a composite, which would be a bug
+///
+/// # Example
+/// ```
+/// use facto::sieve::nth_prime;
+/// assert_eq!(nth_prime(1), Some(2));
+/// assert_eq!(nth_prime(1_000_000), Some(15_485_863));
+/// assert_eq!(nth_prime(0), None);
+/// ```
+#[allow(
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
+pub fn nth_prime(n: u64) -> Option<u64> {
+    if n == 0 {
+        return None;
+    }
+    if n < 1000 {
+        return primes().nth(n as usize - 1);
+    }
+    // Lower bound by Dusart, p_n ≥ n (ln n + ln ln n - 1 + (ln ln n - 2.1) / ln n) for n ≥ 3,
+    // lowered by a margin covering rounding errors
+    let ln = (n as f64).ln();
+    let bound = n as f64 * (ln + ln.ln() - 1.0 + (ln.ln() - 2.1) / ln);
+    let low = (bound * (1.0 - 1e-9)) as u64;
+    let below = prime_count(low);
+    let p = primes_in(low + 1..u64::MAX).nth((n - below - 1) as usize)?;
+    assert!(p.is_prime(), "Sieve found composite {}", p);
+    Some(p)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{nth_prime, prime_count, prime_sum};
+    use crate::sieve::primes;
+
+    #[test]
+    fn small_values() {
+        let small: Vec<u64> = primes().take_while(|p| *p < 5000).collect();
+        for x in 0..5000 {
+            let below: Vec<u64> = small.iter().copied().filter(|p| *p <= x).collect();
+            assert_eq!(prime_count(x), below.len() as u64, "pi({x})");
+            assert_eq!(
+                prime_sum(x),
+                below.iter().map(|p| u128::from(*p)).sum::<u128>(),
+                "sum({x})"
+            );
+        }
+        for (n, p) in small.iter().enumerate() {
+            assert_eq!(nth_prime(n as u64 + 1), Some(*p));
+        }
+    }
+
+    #[test]
+    fn powers_of_ten() {
+        let counts = [
+            4,
+            25,
+            168,
+            1229,
+            9592,
+            78498,
+            664_579,
+            5_761_455,
+            50_847_534,
+            455_052_511,
+        ];
+        let mut x = 1;
+        for count in counts {
+            x *= 10;
+            assert_eq!(prime_count(x), count);
+            assert_eq!(
+                prime_count(x + 1),
+                count + u64::from(x + 1 == 11 || x + 1 == 101)
+            );
+        }
+        assert_eq!(prime_sum(1_000_000_000), 24_739_512_092_254_535);
+        assert_eq!(nth_prime(10_000_000), Some(179_424_673));
+        assert_eq!(nth_prime(50_847_534), Some(999_999_937));
+        assert_eq!(nth_prime(50_847_535), Some(1_000_000_007));
+    }
+}
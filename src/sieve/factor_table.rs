@@ -0,0 +1,270 @@
+use std::convert::TryFrom;
+use std::ops::Range;
+
+use super::segmented::{small_primes, DELTAS, RESIDUES, RESIDUE_BITS};
+use super::{primes, Primes};
+use crate::util::NumUtil;
+use crate::Factorization;
+
+/// Numbers factored at once when sieving ranges
+pub(super) const SEGMENT_LENGTH: u64 = 1 << 16;
+
+/// Position of a number coprime to 30 among the numbers coprime to 30
+#[allow(clippy::cast_possible_truncation)]
+const fn wheel_index(n: u64) -> usize {
+    (n / 30) as usize * 8 + RESIDUE_BITS[(n % 30) as usize].trailing_zeros() as usize
+}
+
+#[derive(Debug, Clone)]
+/// Table of the smallest prime factors of all numbers up to a limit, factoring them in O(log n)
+///
+/// Only the numbers coprime to 30 are stored, with their smallest prime factor fitting into 16 bits,
+/// so the table takes about half a byte per number.
+///
+/// # Example
+/// ```
+/// use facto::sieve::FactorTable;
+/// let table = FactorTable::new(1_000_000);
+/// assert_eq!(table.factor(360_360), Some(vec![2, 2, 2, 3, 3, 5, 7, 11, 13]));
+/// assert_eq!(table.smallest_prime_factor(999_997), Some(757));
+/// assert_eq!(table.factor(1_000_001), None);
+/// ```
+pub struct FactorTable {
+    limit: u64,
+    /// Smallest prime factor of each number coprime to 30, zero for primes
+    table: Vec<u16>,
+}
+
+impl FactorTable {
+    #[must_use]
+    /// Sieve the smallest prime factors of all numbers up to and including `limit`
+    ///
+    /// # Panics
+    /// If `limit` is not below 2^32
+    pub fn new(limit: u64) -> Self {
+        assert!(limit < 1 << 32, "Factor table limit exceeds 2^32");
+        let mut table = vec![0; wheel_index(limit - limit % 30 + 29) + 1];
+        for p in small_primes(limit.integer_square_root() + 1) {
+            let mut wheel = wheel_index(p) % RESIDUES.len();
+            let mut multiple = p * p;
+            while multiple <= limit {
+                let entry = &mut table[wheel_index(multiple)];
+                if *entry == 0 {
+                    *entry = u16::try_from(p).unwrap_or_else(|_| unreachable!());
+                }
+                multiple += p * DELTAS[wheel];
+                wheel = (wheel + 1) % RESIDUES.len();
+            }
+        }
+        Self { limit, table }
+    }
+
+    #[must_use]
+    /// Largest number covered by the table
+    pub const fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    #[must_use]
+    /// Smallest prime factor of `n`
+    ///
+    /// # Returns
+    /// `None` if `n` is below 2 or above the limit
+    pub fn smallest_prime_factor(&self, n: u64) -> Option<u64> {
+        if n < 2 || n > self.limit {
+            return None;
+        }
+        if let Some(p) = [2, 3, 5].iter().copied().find(|p| n % p == 0) {
+            return Some(p);
+        }
+        Some(self.wheel_factor(n))
+    }
+
+    /// Smallest prime factor of `n`, which has to be coprime to 30
+    fn wheel_factor(&self, n: u64) -> u64 {
+        match self.table[wheel_index(n)] {
+            0 => n,
+            p => u64::from(p),
+        }
+    }
+
+    #[must_use]
+    /// Prime factors of `n` in ascending order, see [`crate::Factoring::factor`]
+    ///
+    /// # Returns
+    /// `None` if `n` is zero or above the limit
+    pub fn factor(&self, mut n: u64) -> Option<Vec<u64>> {
+        if n == 0 || n > self.limit {
+            return None;
+        }
+        let mut primes = vec![];
+        for p in [2, 3, 5] {
+            while n % p == 0 {
+                primes.push(p);
+                n /= p;
+            }
+        }
+        while n > 1 {
+            let p = self.wheel_factor(n);
+            primes.push(p);
+            n /= p;
+        }
+        Some(primes)
+    }
+
+    #[must_use]
+    /// Factorization of `n`, see [`crate::Factoring::factor_exponents`]
+    ///
+    /// # Returns
+    /// `None` if `n` is zero or above the limit
+    pub fn factor_exponents(&self, n: u64) -> Option<Factorization<u64>> {
+        self.factor(n).map(Factorization::from_primes)
+    }
+}
+
+/// Split every number in `start..end` into prime powers, calling `visit` with the index of the number, a prime and its exponent
+///
+/// `sieving_primes` has to contain all primes up to the square root of the last number.
+/// The primes of each number are visited in ascending order.
+pub(super) fn visit_prime_powers(
+    start: u64,
+    end: u64,
+    sieving_primes: &[u64],
+    mut visit: impl FnMut(usize, u64, u32),
+) {
+    let bound = (end - 1).integer_square_root();
+    let mut rest: Vec<u64> = (start..end).collect();
+    for &p in sieving_primes.iter().take_while(|p| **p <= bound) {
+        let mut multiple = start.checked_add((p - start % p) % p);
+        while let Some(n) = multiple.filter(|n| *n < end) {
+            #[allow(clippy::cast_possible_truncation)]
+            let i = (n - start) as usize;
+            let mut exponent = 0;
+            while rest[i] % p == 0 {
+                rest[i] /= p;
+                exponent += 1;
+            }
+            visit(i, p, exponent);
+            multiple = n.checked_add(p);
+        }
+    }
+    for (i, rest) in rest.into_iter().enumerate() {
+        // The rest has no prime factor up to the square root of the number, so it is a prime
+        if rest > 1 {
+            visit(i, rest, 1);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Iterator over the factorizations of all numbers in a range, in ascending order
+///
+/// Created by [`factor_range`].
+pub struct RangeFactorizations {
+    next: u64,
+    end: u64,
+    primes: Primes,
+    sieving_primes: Vec<u64>,
+    current: std::vec::IntoIter<(u64, Factorization<u64>)>,
+}
+
+impl RangeFactorizations {
+    fn factor_next_segment(&mut self) {
+        let start = self.next;
+        let end = start.saturating_add(SEGMENT_LENGTH).min(self.end);
+        let bound = (end - 1).integer_square_root();
+        while self.sieving_primes.last().copied().unwrap_or(0) <= bound {
+            match self.primes.next() {
+                Some(p) => self.sieving_primes.push(p),
+                None => break,
+            }
+        }
+        let mut factors: Vec<Vec<(u64, u32)>> = (start..end).map(|_| vec![]).collect();
+        visit_prime_powers(start, end, &self.sieving_primes, |i, p, e| {
+            factors[i].push((p, e));
+        });
+        self.current = (start..end)
+            .zip(factors)
+            .map(|(n, factors)| (n, Factorization::from_factors(factors)))
+            .collect::<Vec<_>>()
+            .into_iter();
+        self.next = end;
+    }
+}
+
+impl Iterator for RangeFactorizations {
+    type Item = (u64, Factorization<u64>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.current.next() {
+                return Some(item);
+            }
+            if self.next >= self.end {
+                return None;
+            }
+            self.factor_next_segment();
+        }
+    }
+}
+
+#[must_use]
+/// Factorizations of all numbers in `range`, by sieving segments of the range with the primes up to the square root
+///
+/// # Panics
+/// If `range` contains zero
+///
+/// # Example
+/// ```
+/// use facto::sieve::factor_range;
+/// let f: Vec<String> = factor_range(10..14).map(|(_, f)| f.to_string()).collect();
+/// assert_eq!(f, ["2 * 5", "11", "2^2 * 3", "13"]);
+/// ```
+pub fn factor_range(range: Range<u64>) -> RangeFactorizations {
+    assert!(range.start > 0 || range.is_empty(), "Can not factor zero");
+    RangeFactorizations {
+        next: range.start,
+        end: range.end,
+        primes: primes(),
+        sieving_primes: vec![],
+        current: Vec::new().into_iter(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{factor_range, FactorTable};
+    use crate::Factoring;
+
+    #[test]
+    fn table() {
+        let table = FactorTable::new(100_000);
+        assert_eq!(table.factor(0), None);
+        assert_eq!(table.factor(1), Some(vec![]));
+        assert_eq!(table.smallest_prime_factor(1), None);
+        for n in 2..=100_000u64 {
+            let factors = n.factor();
+            assert_eq!(table.factor(n).as_ref(), Some(&factors), "{n}");
+            assert_eq!(table.smallest_prime_factor(n), Some(factors[0]));
+        }
+        assert_eq!(table.factor(100_001), None);
+
+        // Limits not aligned to the wheel
+        for limit in [0, 1, 48, 49, 120, 121] {
+            let table = FactorTable::new(limit);
+            for n in 2..=limit {
+                assert_eq!(table.factor(n), Some(n.factor()), "{n} up to {limit}");
+            }
+        }
+    }
+
+    #[test]
+    fn range() {
+        let start = 1_000_000_000_000u64;
+        for (n, f) in factor_range(start..start + 200_000) {
+            assert_eq!(f, n.factor_exponents(), "{n}");
+        }
+        let found: Vec<u64> = factor_range(1..1000).map(|(n, _)| n).collect();
+        assert_eq!(found, (1..1000).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,21 @@
+mod constellation;
+mod counting;
+mod factor_table;
+mod multiplicative;
+mod polynomial;
+mod segmented;
+mod summatory;
+
+pub use constellation::{
+    ConstellationSearch, CunninghamKind, PrimeConstellation, SearchInteger, PRIME_QUADRUPLETS,
+    PRIME_TRIPLETS, TWIN_PRIMES,
+};
+pub use counting::{nth_prime, prime_count, prime_sum};
+pub use factor_table::{factor_range, FactorTable, RangeFactorizations};
+pub use multiplicative::{
+    multiplicative_table, omega_table, EulerPhi, Liouville, Moebius, MultiplicativeFunction, Sigma,
+    Tau,
+};
+pub use polynomial::{PolynomialFactorizations, PolynomialPrimes, PolynomialSieve};
+pub use segmented::{primes, primes_in, IntegerPrimes, PrimeSieve, Primes};
+pub use summatory::{divisor_summatory, mertens, sigma_summatory, totient_summatory};
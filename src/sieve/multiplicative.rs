@@ -0,0 +1,236 @@
+use std::ops::Range;
+
+use num_traits::One;
+
+use super::factor_table::{visit_prime_powers, SEGMENT_LENGTH};
+use super::primes_in;
+use crate::util::{power, NumUtil};
+
+/// Multiplicative function, defined by its values at prime powers
+///
+/// Implemented for closures taking a prime and an exponent, to define custom functions.
+///
+/// # Example
+/// ```
+/// use facto::sieve::multiplicative_table;
+/// // Number of squares dividing n
+/// let squares = multiplicative_table(1..13, &|_: u64, e: u32| u64::from(e / 2 + 1));
+/// assert_eq!(squares, [1, 1, 1, 2, 1, 1, 1, 2, 2, 1, 1, 2]);
+/// ```
+pub trait MultiplicativeFunction {
+    /// Type of the values, the value at 1 being one
+    type Value: One + Clone;
+
+    /// Value at `p` to the power of `e`, for a prime `p` and a positive `e`
+    fn prime_power(&self, p: u64, e: u32) -> Self::Value;
+}
+
+impl<V: One + Clone, F: Fn(u64, u32) -> V> MultiplicativeFunction for F {
+    type Value = V;
+
+    fn prime_power(&self, p: u64, e: u32) -> V {
+        self(p, e)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Euler's totient φ, see [`crate::Factorization::euler_phi`]
+pub struct EulerPhi;
+
+impl MultiplicativeFunction for EulerPhi {
+    type Value = u64;
+
+    fn prime_power(&self, p: u64, e: u32) -> u64 {
+        power(p, e - 1) * (p - 1)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Möbius function μ, see [`crate::Factorization::moebius`]
+pub struct Moebius;
+
+impl MultiplicativeFunction for Moebius {
+    type Value = i8;
+
+    fn prime_power(&self, _p: u64, e: u32) -> i8 {
+        if e == 1 {
+            -1
+        } else {
+            0
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Liouville's function λ, -1 to the power of the number of prime factors counted with multiplicity
+pub struct Liouville;
+
+impl MultiplicativeFunction for Liouville {
+    type Value = i8;
+
+    fn prime_power(&self, _p: u64, e: u32) -> i8 {
+        if e % 2 == 1 {
+            -1
+        } else {
+            1
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Divisor function `σ_k` with the contained k, see [`crate::Factorization::sigma`]
+pub struct Sigma(pub u32);
+
+impl MultiplicativeFunction for Sigma {
+    type Value = u64;
+
+    fn prime_power(&self, p: u64, e: u32) -> u64 {
+        let p_k = power(p, self.0);
+        let mut sum = 1;
+        let mut term = 1;
+        for _ in 0..e {
+            term *= p_k;
+            sum += term;
+        }
+        sum
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Number of divisors τ, see [`crate::Factorization::tau`]
+pub struct Tau;
+
+impl MultiplicativeFunction for Tau {
+    type Value = u64;
+
+    fn prime_power(&self, _p: u64, e: u32) -> u64 {
+        u64::from(e) + 1
+    }
+}
+
+/// Split `range` into segments, calling `sieve` with each segment and the primes up to the square root of the range's end
+fn sieve_segments(range: &Range<u64>, mut sieve: impl FnMut(Range<u64>, &[u64])) {
+    assert!(
+        range.start > 0 || range.is_empty(),
+        "Arithmetic functions of 0 are undefined"
+    );
+    if range.is_empty() {
+        return;
+    }
+    let sieving_primes: Vec<u64> =
+        primes_in(2..(range.end - 1).integer_square_root() + 1).collect();
+    let mut start = range.start;
+    while start < range.end {
+        let end = start.saturating_add(SEGMENT_LENGTH).min(range.end);
+        sieve(start..end, &sieving_primes);
+        start = end;
+    }
+}
+
+/// Call `consume` with every number in `range` and the value of `f` at it, in ascending order
+///
+/// Only the values of one segment are kept in memory at a time.
+pub(super) fn for_each_value<F: MultiplicativeFunction + ?Sized>(
+    range: &Range<u64>,
+    f: &F,
+    mut consume: impl FnMut(u64, F::Value),
+) {
+    sieve_segments(range, |segment, sieving_primes| {
+        let mut values: Vec<F::Value> = segment.clone().map(|_| F::Value::one()).collect();
+        visit_prime_powers(segment.start, segment.end, sieving_primes, |i, p, e| {
+            values[i] = values[i].clone() * f.prime_power(p, e);
+        });
+        for (n, value) in segment.zip(values) {
+            consume(n, value);
+        }
+    });
+}
+
+#[must_use]
+/// Values of the multiplicative function `f` for all numbers in `range`, in ascending order
+///
+/// The range is sieved in segments by the primes up to the square root of its end,
+/// multiplying the values at the prime powers of each number.
+///
+/// # Panics
+/// If `range` contains zero
+///
+/// # Example
+/// ```
+/// use facto::sieve::{multiplicative_table, EulerPhi, Moebius, Sigma};
+/// assert_eq!(multiplicative_table(1..9, &EulerPhi), [1, 1, 2, 2, 4, 2, 6, 4]);
+/// assert_eq!(multiplicative_table(1..9, &Moebius), [1, -1, -1, 0, -1, 1, -1, 0]);
+/// assert_eq!(multiplicative_table(1..9, &Sigma(1)), [1, 3, 4, 7, 6, 12, 8, 15]);
+/// ```
+pub fn multiplicative_table<F: MultiplicativeFunction + ?Sized>(
+    range: Range<u64>,
+    f: &F,
+) -> Vec<F::Value> {
+    let mut table = vec![];
+    for_each_value(&range, f, |_, value| table.push(value));
+    table
+}
+
+#[must_use]
+/// Number of distinct prime factors ω for all numbers in `range`, in ascending order
+///
+/// ω is additive instead of multiplicative, but sieved the same way as [`multiplicative_table`].
+///
+/// # Panics
+/// If `range` contains zero
+///
+/// # Example
+/// ```
+/// use facto::sieve::omega_table;
+/// assert_eq!(omega_table(28..32), [2, 1, 3, 1]);
+/// ```
+pub fn omega_table(range: Range<u64>) -> Vec<u64> {
+    let mut table = vec![];
+    sieve_segments(&range, |segment, sieving_primes| {
+        let offset = table.len();
+        table.extend(segment.clone().map(|_| 0));
+        visit_prime_powers(segment.start, segment.end, sieving_primes, |i, _, _| {
+            table[offset + i] += 1;
+        });
+    });
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{multiplicative_table, omega_table, EulerPhi, Liouville, Moebius, Sigma, Tau};
+    use crate::arithmetic::ArithmeticFunctions;
+    use crate::Factoring;
+
+    #[test]
+    fn builtin_functions() {
+        for range in [1..3000, 10_000_000_000..10_000_020_000] {
+            let phi = multiplicative_table(range.clone(), &EulerPhi);
+            let moebius = multiplicative_table(range.clone(), &Moebius);
+            let liouville = multiplicative_table(range.clone(), &Liouville);
+            let sigma = multiplicative_table(range.clone(), &Sigma(1));
+            let tau = multiplicative_table(range.clone(), &Tau);
+            let omega = omega_table(range.clone());
+            for (i, n) in range.enumerate() {
+                let f = n.factor_exponents();
+                assert_eq!(phi[i], f.euler_phi(), "phi({n})");
+                assert_eq!(moebius[i], f.moebius(), "mu({n})");
+                let expected = if f.big_omega() % 2 == 0 { 1 } else { -1 };
+                assert_eq!(liouville[i], expected, "lambda({n})");
+                assert_eq!(sigma[i], f.sigma(1), "sigma({n})");
+                assert_eq!(tau[i], f.tau(), "tau({n})");
+                assert_eq!(omega[i], f.omega(), "omega({n})");
+            }
+        }
+        assert!(multiplicative_table(5..5, &Tau).is_empty());
+    }
+
+    #[test]
+    fn custom_function() {
+        // Radical as a multiplicative function
+        let radical = multiplicative_table(1..1000, &|p: u64, _: u32| p);
+        for (n, r) in (1..1000u64).zip(radical) {
+            assert_eq!(r, n.radical());
+        }
+    }
+}
@@ -0,0 +1,298 @@
+use std::convert::TryFrom;
+use std::ops::Range;
+
+use rug::Integer;
+
+use super::primes_in;
+use crate::{Factoring, Factorization, Primality};
+
+/// Numbers whose polynomial values are sieved at once
+const SEGMENT_LENGTH: u64 = 1 << 12;
+
+/// Default exclusive bound of the sieving primes
+const DEFAULT_BOUND: u64 = 1 << 12;
+
+#[derive(Debug, Clone)]
+/// Sieve over the values f(n) of a polynomial f with integer coefficients, at all n of a range
+///
+/// The roots of f modulo every prime p below a bound are found once, the values divisible by p are then exactly
+/// those at n congruent to a root. Sieving a segment of values with them removes or divides out all small factors,
+/// only the survivors are checked using [`Primality::is_prime`] or factored using [`Factoring::factor_exponents`].
+///
+/// Values that are not positive are skipped.
+///
+/// # Example
+/// ```
+/// use facto::sieve::PolynomialSieve;
+/// // n² + 1
+/// let sieve = PolynomialSieve::new(&[1, 0, 1]);
+/// let found: Vec<u64> = sieve.primes(0..30).map(|(n, _)| n).collect();
+/// assert_eq!(found, [1, 2, 4, 6, 10, 14, 16, 20, 24, 26]);
+/// let (n, f) = sieve.factorizations(7..8).next().unwrap();
+/// assert_eq!((n, f.to_string()), (7, "2 * 5^2".to_string()));
+/// ```
+pub struct PolynomialSieve {
+    /// Coefficients, starting with the constant one
+    coefficients: Vec<i64>,
+    bound: u64,
+    /// Sieving primes with the roots of the polynomial modulo them
+    roots: Vec<(u64, Vec<u64>)>,
+}
+
+impl PolynomialSieve {
+    #[must_use]
+    /// Sieve for the polynomial with the given `coefficients`, starting with the constant one, by the primes below 2^12
+    pub fn new(coefficients: &[i64]) -> Self {
+        Self::with_bound(coefficients, DEFAULT_BOUND)
+    }
+
+    #[must_use]
+    /// Sieve for the polynomial with the given `coefficients`, starting with the constant one, by the primes below `bound`
+    ///
+    /// The roots modulo each prime are found by evaluating the polynomial at all residues,
+    /// taking O(bound² / log bound) time for all primes.
+    ///
+    /// # Panics
+    /// If `bound` exceeds 2^20
+    pub fn with_bound(coefficients: &[i64], bound: u64) -> Self {
+        assert!(bound <= 1 << 20, "Sieve bound exceeds 2^20");
+        let roots = primes_in(2..bound.max(2))
+            .map(|p| {
+                let modulus = i64::try_from(p).unwrap_or_else(|_| unreachable!());
+                let reduced: Vec<u64> = coefficients
+                    .iter()
+                    .map(|c| c.rem_euclid(modulus).unsigned_abs())
+                    .collect();
+                let roots = (0..p)
+                    .filter(|x| reduced.iter().rev().fold(0, |acc, c| (acc * x + c) % p) == 0)
+                    .collect();
+                (p, roots)
+            })
+            .collect();
+        Self {
+            coefficients: coefficients.to_vec(),
+            bound,
+            roots,
+        }
+    }
+
+    #[must_use]
+    /// Value of the polynomial at `n`
+    pub fn value(&self, n: u64) -> Integer {
+        self.coefficients
+            .iter()
+            .rev()
+            .fold(Integer::new(), |acc, c| acc * n + *c)
+    }
+
+    /// Values of the polynomial in `start..end`, visiting the sieving primes dividing each positive value
+    ///
+    /// `visit` is called with the value, its index and a sieving prime dividing it, for the primes in ascending order.
+    fn sieve_segment(
+        &self,
+        start: u64,
+        end: u64,
+        mut visit: impl FnMut(&mut Integer, usize, u64),
+    ) -> Vec<Integer> {
+        let mut values: Vec<Integer> = (start..end).map(|n| self.value(n)).collect();
+        for (p, roots) in &self.roots {
+            for r in roots {
+                let mut multiple = start.checked_add((r + p - start % p) % p);
+                while let Some(n) = multiple.filter(|n| *n < end) {
+                    #[allow(clippy::cast_possible_truncation)]
+                    let i = (n - start) as usize;
+                    if values[i] > 0 {
+                        visit(&mut values[i], i, *p);
+                    }
+                    multiple = n.checked_add(*p);
+                }
+            }
+        }
+        values
+    }
+
+    #[must_use]
+    /// Iterator over the n in `range` with a prime value f(n), together with the value
+    pub fn primes(&self, range: Range<u64>) -> PolynomialPrimes {
+        PolynomialPrimes {
+            sieve: self.clone(),
+            next: range.start,
+            end: range.end,
+            current: Vec::new().into_iter(),
+        }
+    }
+
+    #[must_use]
+    /// Iterator over the n in `range` with a positive value f(n), together with the value's factorization
+    ///
+    /// The small prime factors are divided out while sieving, the rest is factored.
+    pub fn factorizations(&self, range: Range<u64>) -> PolynomialFactorizations {
+        PolynomialFactorizations {
+            sieve: self.clone(),
+            next: range.start,
+            end: range.end,
+            current: Vec::new().into_iter(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Iterator over the n with prime polynomial values, created by [`PolynomialSieve::primes`]
+pub struct PolynomialPrimes {
+    sieve: PolynomialSieve,
+    next: u64,
+    end: u64,
+    current: std::vec::IntoIter<(u64, Integer)>,
+}
+
+impl PolynomialPrimes {
+    fn sieve_next_segment(&mut self) {
+        let start = self.next;
+        let end = start.saturating_add(SEGMENT_LENGTH).min(self.end);
+        #[allow(clippy::cast_possible_truncation)]
+        let mut composite = vec![false; (end - start) as usize];
+        let values = self.sieve.sieve_segment(start, end, |value, i, p| {
+            // The value may be the sieving prime itself
+            if *value > p {
+                composite[i] = true;
+            }
+        });
+        self.current = (start..end)
+            .zip(values)
+            .zip(composite)
+            .filter(|((_, value), composite)| !composite && *value > 1)
+            .map(|(item, _)| item)
+            .filter(|(_, value)| value.clone().is_prime())
+            .collect::<Vec<_>>()
+            .into_iter();
+        self.next = end;
+    }
+}
+
+impl Iterator for PolynomialPrimes {
+    type Item = (u64, Integer);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.current.next() {
+                return Some(item);
+            }
+            if self.next >= self.end {
+                return None;
+            }
+            self.sieve_next_segment();
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Iterator over the factorizations of polynomial values, created by [`PolynomialSieve::factorizations`]
+pub struct PolynomialFactorizations {
+    sieve: PolynomialSieve,
+    next: u64,
+    end: u64,
+    current: std::vec::IntoIter<(u64, Factorization<Integer>)>,
+}
+
+impl PolynomialFactorizations {
+    fn factor_next_segment(&mut self) {
+        let start = self.next;
+        let end = start.saturating_add(SEGMENT_LENGTH).min(self.end);
+        let mut factors: Vec<Vec<(Integer, u32)>> = (start..end).map(|_| vec![]).collect();
+        let rests = self.sieve.sieve_segment(start, end, |rest, i, p| {
+            let divisor = u32::try_from(p).unwrap_or_else(|_| unreachable!());
+            let mut exponent = 0;
+            while rest.is_divisible_u(divisor) {
+                rest.div_exact_u_mut(divisor);
+                exponent += 1;
+            }
+            factors[i].push((Integer::from(p), exponent));
+        });
+        let square = Integer::from(self.sieve.bound) * self.sieve.bound;
+        self.current = (start..end)
+            .zip(rests)
+            .zip(factors)
+            .filter(|((_, rest), _)| *rest > 0)
+            .map(|((n, rest), mut factors)| {
+                // The rest has no prime factor below the bound, so it is a prime if below its square
+                if rest < square || rest.clone().is_prime() {
+                    if rest > 1 {
+                        factors.push((rest, 1));
+                    }
+                } else {
+                    factors.extend(rest.factor_exponents());
+                }
+                (n, Factorization::from_factors(factors))
+            })
+            .collect::<Vec<_>>()
+            .into_iter();
+        self.next = end;
+    }
+}
+
+impl Iterator for PolynomialFactorizations {
+    type Item = (u64, Factorization<Integer>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.current.next() {
+                return Some(item);
+            }
+            if self.next >= self.end {
+                return None;
+            }
+            self.factor_next_segment();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PolynomialSieve;
+    use crate::{Factoring, Integer, Primality};
+
+    #[test]
+    fn primes() {
+        for coefficients in [
+            &[1, 0, 1][..],
+            &[-1, 0, 2],
+            &[41, 1, 1],
+            &[3, 0, 0, 1],
+            &[0, 0, 1],
+        ] {
+            let sieve = PolynomialSieve::new(coefficients);
+            for range in [0..20_000, 1_000_000_000..1_000_010_000] {
+                let expected: Vec<(u64, Integer)> = range
+                    .clone()
+                    .map(|n| (n, sieve.value(n)))
+                    .filter(|(_, value)| *value > 1 && value.clone().is_prime())
+                    .collect();
+                assert_eq!(
+                    sieve.primes(range).collect::<Vec<_>>(),
+                    expected,
+                    "{coefficients:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn factorizations() {
+        for coefficients in [&[1, 0, 1][..], &[-1, 0, 2], &[6, 6, 0, 6], &[-100, 1]] {
+            let sieve = PolynomialSieve::with_bound(coefficients, 100);
+            for range in [0..3000, 1_000_000..1_001_000] {
+                let expected: Vec<_> = range
+                    .clone()
+                    .map(|n| (n, sieve.value(n)))
+                    .filter(|(_, value)| *value > 0)
+                    .map(|(n, value)| (n, value.factor_exponents()))
+                    .collect();
+                assert_eq!(
+                    sieve.factorizations(range).collect::<Vec<_>>(),
+                    expected,
+                    "{coefficients:?}"
+                );
+            }
+        }
+    }
+}
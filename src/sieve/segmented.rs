@@ -0,0 +1,440 @@
+use std::convert::TryFrom;
+use std::ops::Range;
+
+use rug::Integer;
+
+use crate::util::NumUtil;
+use crate::Primality;
+
+/// Number of bytes sieved at once, each byte covering 30 numbers, sized to fit the L1 cache
+const SEGMENT_BYTES: usize = 1 << 15;
+
+/// Residues modulo 30 coprime to 30, one bit of a sieve byte each
+pub(super) const RESIDUES: [u64; 8] = [1, 7, 11, 13, 17, 19, 23, 29];
+
+/// Distance from each residue to the next one
+pub(super) const DELTAS: [u64; 8] = [6, 4, 2, 4, 2, 4, 6, 2];
+
+/// Exclusive bound of the primes sieved with a plain sieve, a multiple of 30 exceeding 2^16
+const SMALL_LIMIT: u64 = 65_550;
+
+/// Largest prime stored for sieving `u64` ranges, larger numbers are checked by [`Primality::is_prime`]
+const MAX_BASE_PRIME: u64 = 1 << 22;
+
+#[allow(clippy::cast_possible_truncation)]
+/// Bit of the sieve byte representing each residue modulo 30, zero for residues not coprime to 30
+pub(super) const RESIDUE_BITS: [u8; 30] = {
+    let mut bits = [0; 30];
+    let mut i = 0;
+    while i < RESIDUES.len() {
+        bits[RESIDUES[i] as usize] = 1 << i;
+        i += 1;
+    }
+    bits
+};
+
+/// Index of the smallest residue not below each number up to 30, with 8 standing for 31
+const NEXT_RESIDUE: [usize; 31] = {
+    let mut next = [0; 31];
+    let mut r = 0;
+    while r < 31 {
+        let mut i = 0;
+        while i < RESIDUES.len() && RESIDUES[i] < r as u64 {
+            i += 1;
+        }
+        next[r] = i;
+        r += 1;
+    }
+    next
+};
+
+#[allow(clippy::cast_possible_truncation)]
+/// Inverse modulo 30 of each residue coprime to 30
+const INVERSES: [u64; 30] = {
+    let mut inverses = [0; 30];
+    let mut i = 0;
+    while i < RESIDUES.len() {
+        let mut x = 1;
+        while x * RESIDUES[i] % 30 != 1 {
+            x += 1;
+        }
+        inverses[RESIDUES[i] as usize] = x;
+        i += 1;
+    }
+    inverses
+};
+
+/// Primes p with 7 ≤ p < `limit`, by a plain sieve of eratosthenes
+pub(super) fn small_primes(limit: u64) -> Vec<u64> {
+    let limit = usize::try_from(limit).expect("Small sieve limit exceeds address space");
+    let mut composite = vec![false; limit];
+    let mut result = vec![];
+    for n in 2..limit {
+        if composite[n] {
+            continue;
+        }
+        if n >= 7 {
+            result.push(n as u64);
+        }
+        for multiple in (n * n..limit).step_by(n) {
+            composite[multiple] = true;
+        }
+    }
+    result
+}
+
+/// Offset from `start` of the first multiple of `p` to cross off, skipping `p` itself
+const fn first_offset(start: u64, p: u64) -> u64 {
+    if p * p >= start {
+        p * p - start
+    } else {
+        (p - start % p) % p
+    }
+}
+
+/// Clear the bits of all multiples p * m with m coprime to 30, starting `offset` numbers into `segment`
+///
+/// The segment has to start at a multiple of 30, so residues of offsets equal the residues of the numbers.
+#[allow(clippy::cast_possible_truncation)]
+fn cross_off(segment: &mut [u8], p: u64, offset: u64) {
+    let len = segment.len() as u64 * 30;
+    // The multiplier m of the first multiple, rounded up to the wheel
+    let m = offset % 30 * INVERSES[(p % 30) as usize] % 30;
+    let mut wheel = NEXT_RESIDUE[m as usize];
+    let mut offset = offset + p * (RESIDUES.get(wheel).copied().unwrap_or(31) - m);
+    wheel %= RESIDUES.len();
+    while offset < len {
+        segment[(offset / 30) as usize] &= !RESIDUE_BITS[(offset % 30) as usize];
+        offset += p * DELTAS[wheel];
+        wheel = (wheel + 1) % RESIDUES.len();
+    }
+}
+
+/// Offsets of the numbers not crossed off in `segment`, ascending
+fn survivors(segment: &[u8]) -> impl Iterator<Item = u64> + '_ {
+    segment.iter().enumerate().flat_map(|(i, byte)| {
+        RESIDUES
+            .iter()
+            .enumerate()
+            .filter(move |(bit, _)| byte >> bit & 1 == 1)
+            .map(move |(_, r)| i as u64 * 30 + r)
+    })
+}
+
+/// Wheel sieve of `bytes * 30` numbers starting at `start`, a multiple of 30
+///
+/// Crosses off multiples of those `primes` not exceeding the square root of the last number, `1` is crossed off as well.
+fn sieve_segment(start: u64, bytes: usize, primes: &[u64]) -> Vec<u8> {
+    let mut segment = vec![u8::MAX; bytes];
+    let last = start.saturating_add(bytes as u64 * 30 - 1);
+    let bound = last.integer_square_root();
+    for &p in primes.iter().take_while(|&&p| p <= bound) {
+        cross_off(&mut segment, p, first_offset(start, p));
+    }
+    if start == 0 {
+        segment[0] &= !1;
+    }
+    segment
+}
+
+/// Primes of the range not covered by the wheel
+fn wheel_primes<T: From<u8> + PartialOrd>(range: &Range<T>) -> std::vec::IntoIter<T> {
+    [2u8, 3, 5]
+        .iter()
+        .map(|&p| T::from(p))
+        .filter(|p| range.contains(p))
+        .collect::<Vec<_>>()
+        .into_iter()
+}
+
+#[derive(Debug, Clone)]
+/// Iterator over the primes in a range of `u64`, in ascending order
+///
+/// Created by [`primes`] or [`primes_in`].
+/// The primes needed for sieving are themselves sieved once they are needed, up to 2^22.
+/// Survivors above 2^44 are checked using [`Primality::is_prime`] one at a time.
+pub struct Primes {
+    end: u64,
+    start: u64,
+    segment_start: Option<u64>,
+    sieving: Vec<u64>,
+    sieving_limit: u64,
+    wheel: std::vec::IntoIter<u64>,
+    candidates: std::vec::IntoIter<u64>,
+}
+
+impl Primes {
+    fn new(range: Range<u64>) -> Self {
+        Self {
+            end: range.end,
+            start: range.start,
+            segment_start: (range.start < range.end).then(|| range.start - range.start % 30),
+            sieving: vec![],
+            sieving_limit: 0,
+            wheel: wheel_primes(&range),
+            candidates: Vec::new().into_iter(),
+        }
+    }
+
+    /// Make sure all primes up to `limit` are in the sieving primes
+    fn extend_sieving(&mut self, limit: u64) {
+        if limit < self.sieving_limit {
+            return;
+        }
+        if limit < SMALL_LIMIT {
+            // Doubling keeps the number of plain sieves logarithmic
+            self.sieving_limit = (limit + 1).max(2 * self.sieving_limit).min(SMALL_LIMIT);
+            self.sieving = small_primes(self.sieving_limit);
+            return;
+        }
+        if self.sieving_limit < SMALL_LIMIT {
+            self.sieving_limit = SMALL_LIMIT;
+            self.sieving = small_primes(SMALL_LIMIT);
+        }
+        while self.sieving_limit <= limit {
+            let start = self.sieving_limit;
+            let segment = sieve_segment(start, SEGMENT_BYTES, &self.sieving);
+            self.sieving
+                .extend(survivors(&segment).map(|offset| start + offset));
+            self.sieving_limit += SEGMENT_BYTES as u64 * 30;
+        }
+    }
+
+    fn sieve_next_segment(&mut self, start: u64) {
+        let bytes = usize::try_from((self.end - start - 1) / 30 + 1)
+            .map_or(SEGMENT_BYTES, |bytes| bytes.min(SEGMENT_BYTES));
+        let length = bytes as u64 * 30;
+        let last = start.saturating_add(length - 1).min(self.end - 1);
+        self.extend_sieving(last.integer_square_root().min(MAX_BASE_PRIME));
+        let segment = sieve_segment(start, bytes, &self.sieving);
+        let range = self.start..self.end;
+        self.candidates = survivors(&segment)
+            .filter_map(|offset| start.checked_add(offset))
+            .filter(|n| range.contains(n))
+            .collect::<Vec<_>>()
+            .into_iter();
+        self.segment_start = start.checked_add(length).filter(|next| *next < self.end);
+    }
+}
+
+impl Iterator for Primes {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        if let Some(p) = self.wheel.next() {
+            return Some(p);
+        }
+        loop {
+            // Composites below sieving_limit² have a prime factor among the sieving primes
+            let proven = self.sieving_limit.saturating_mul(self.sieving_limit);
+            if let Some(p) = self
+                .candidates
+                .by_ref()
+                .find(|&n| n < proven || n.is_prime())
+            {
+                return Some(p);
+            }
+            let start = self.segment_start?;
+            self.sieve_next_segment(start);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Iterator over the primes in a range of [`Integer`], in ascending order
+///
+/// Created by [`primes_in`].
+/// Segments are sieved by the primes below 2^16, the survivors are checked using [`Primality::is_prime`] one at a time.
+pub struct IntegerPrimes {
+    end: Integer,
+    start: Integer,
+    segment_start: Integer,
+    small_primes: Vec<u64>,
+    current_start: Integer,
+    candidates: std::vec::IntoIter<u64>,
+    wheel: std::vec::IntoIter<Integer>,
+}
+
+impl IntegerPrimes {
+    fn new(range: Range<Integer>) -> Self {
+        let range = range.start.max(Integer::new())..range.end;
+        let segment_start = Integer::from(&range.start - range.start.mod_u(30));
+        Self {
+            wheel: wheel_primes(&range),
+            end: range.end,
+            start: range.start,
+            current_start: segment_start.clone(),
+            segment_start,
+            small_primes: small_primes(SMALL_LIMIT),
+            candidates: Vec::new().into_iter(),
+        }
+    }
+
+    fn sieve_next_segment(&mut self) {
+        let remaining = Integer::from(&self.end - &self.segment_start);
+        let bytes = (remaining - 1u32) / 30u32 + 1u32;
+        let bytes = bytes
+            .to_usize()
+            .map_or(SEGMENT_BYTES, |b| b.min(SEGMENT_BYTES));
+        let mut segment = vec![u8::MAX; bytes];
+        let small_start = self.segment_start.to_u64();
+        for &p in &self.small_primes {
+            let offset = small_start.map_or_else(
+                || (p - Integer::from(&self.segment_start % p).to_u64_wrapping()) % p,
+                |start| first_offset(start, p),
+            );
+            cross_off(&mut segment, p, offset);
+        }
+        if self.segment_start == 0 {
+            segment[0] &= !1;
+        }
+        self.candidates = survivors(&segment).collect::<Vec<_>>().into_iter();
+        self.current_start = self.segment_start.clone();
+        self.segment_start += bytes as u64 * 30;
+    }
+}
+
+impl Iterator for IntegerPrimes {
+    type Item = Integer;
+
+    fn next(&mut self) -> Option<Integer> {
+        if let Some(p) = self.wheel.next() {
+            return Some(p);
+        }
+        loop {
+            for offset in self.candidates.by_ref() {
+                let n = Integer::from(&self.current_start + offset);
+                if n < self.start {
+                    continue;
+                }
+                if n >= self.end {
+                    return None;
+                }
+                // Composites below SMALL_LIMIT² have a prime factor below SMALL_LIMIT
+                if n < SMALL_LIMIT * SMALL_LIMIT || n.clone().is_prime() {
+                    return Some(n);
+                }
+            }
+            if self.segment_start >= self.end {
+                return None;
+            }
+            self.sieve_next_segment();
+        }
+    }
+}
+
+/// Integers whose primes can be enumerated by a segmented sieve
+pub trait PrimeSieve: Sized {
+    /// Iterator over the primes
+    type Primes: Iterator<Item = Self>;
+
+    /// Primes in `range`, in ascending order
+    fn primes_in(range: Range<Self>) -> Self::Primes;
+}
+
+impl PrimeSieve for u64 {
+    type Primes = Primes;
+
+    fn primes_in(range: Range<Self>) -> Primes {
+        Primes::new(range)
+    }
+}
+
+impl PrimeSieve for Integer {
+    type Primes = IntegerPrimes;
+
+    fn primes_in(range: Range<Self>) -> IntegerPrimes {
+        IntegerPrimes::new(range)
+    }
+}
+
+#[must_use]
+/// All primes fitting into a `u64`, in ascending order
+///
+/// # Example
+/// ```
+/// let first: Vec<u64> = facto::sieve::primes().take(6).collect();
+/// assert_eq!(first, [2, 3, 5, 7, 11, 13]);
+/// ```
+pub fn primes() -> Primes {
+    Primes::new(0..u64::MAX)
+}
+
+/// Primes in `range`, in ascending order
+///
+/// Numbers are sieved in segments fitting into the cache, using a wheel skipping the multiples of 2, 3 and 5.
+///
+/// # Example
+/// ```
+/// use facto::sieve::primes_in;
+/// use facto::Integer;
+/// assert_eq!(primes_in(90u64..110).collect::<Vec<_>>(), [97, 101, 103, 107, 109]);
+/// let start = Integer::from(Integer::u_pow_u(10, 30));
+/// let end = start.clone() + 100u32;
+/// let found: Vec<Integer> = primes_in(start.clone()..end).collect();
+/// assert_eq!(found, [start.clone() + 57u32, start + 99u32]);
+/// ```
+pub fn primes_in<T: PrimeSieve>(range: Range<T>) -> T::Primes {
+    T::primes_in(range)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{primes, primes_in, SEGMENT_BYTES};
+    use crate::{Integer, Primality};
+
+    #[test]
+    fn small_ranges() {
+        let expected: Vec<u64> = (0..2000u64).filter(|n| n.is_prime()).collect();
+        assert_eq!(
+            primes().take_while(|p| *p < 2000).collect::<Vec<_>>(),
+            expected
+        );
+        for start in 0..60u64 {
+            for end in start..120 {
+                let expected: Vec<u64> = (start..end).filter(|n| n.is_prime()).collect();
+                assert_eq!(primes_in(start..end).collect::<Vec<_>>(), expected);
+                let found: Vec<u64> = primes_in(Integer::from(start)..Integer::from(end))
+                    .map(|p| p.to_u64().unwrap())
+                    .collect();
+                assert_eq!(found, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn segment_boundaries() {
+        let boundary = SEGMENT_BYTES as u64 * 30;
+        assert_eq!(primes_in(0..3 * boundary).count(), 213_390);
+        for k in 1..3 {
+            let range = k * boundary - 1000..k * boundary + 1000;
+            let expected: Vec<u64> = range.clone().filter(|n| n.is_prime()).collect();
+            assert_eq!(primes_in(range.clone()).collect::<Vec<_>>(), expected);
+            let found: Vec<u64> = primes_in(Integer::from(range.start)..Integer::from(range.end))
+                .map(|p| p.to_u64().unwrap())
+                .collect();
+            assert_eq!(found, expected);
+        }
+    }
+
+    #[test]
+    fn large_numbers() {
+        let range = u64::MAX - 10_000..u64::MAX;
+        let expected: Vec<u64> = range.clone().filter(|n| n.is_prime()).collect();
+        assert_eq!(primes_in(range).collect::<Vec<_>>(), expected);
+
+        // Sieving primes beyond the plain sieve are needed above 2^32
+        let range = (1u64 << 40)..(1 << 40) + 5000;
+        let expected: Vec<u64> = range.clone().filter(|n| n.is_prime()).collect();
+        assert_eq!(primes_in(range).collect::<Vec<_>>(), expected);
+
+        // Above 2^128 the expected primes would be certified by factoring
+        let start = Integer::from(1) << 96u32;
+        let end = Integer::from(&start + 3000u32);
+        let expected: Vec<Integer> = (0..3000u32)
+            .map(|k| Integer::from(&start + k))
+            .filter(|n| n.clone().is_prime())
+            .collect();
+        assert_eq!(primes_in(start..end).collect::<Vec<_>>(), expected);
+    }
+}
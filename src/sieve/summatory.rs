@@ -0,0 +1,190 @@
+#![allow(clippy::many_single_char_names)]
+
+use std::ops::{Add, Mul, Sub};
+
+use super::multiplicative::{for_each_value, MultiplicativeFunction};
+use super::{EulerPhi, Moebius};
+use crate::util::NumUtil;
+
+/// Sum of `f(n)` over all n up to `x`, for a function whose dirichlet convolution with 1 has the summatory function `g`
+///
+/// Uses S(x) = G(x) - Σ S(⌊x / d⌋) over 2 ≤ d ≤ x, only needing S at the values ⌊x / m⌋.
+/// The values up to x^(2/3) are summed from segments of the sieved values of `f`,
+/// the larger ones are computed recursively, in O(x^(2/3)) time and O(√x) space.
+#[allow(
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
+fn summatory<F, T>(x: u64, f: &F, g: impl Fn(u64) -> T) -> T
+where
+    F: MultiplicativeFunction,
+    T: Copy
+        + Default
+        + From<u64>
+        + From<F::Value>
+        + Add<Output = T>
+        + Sub<Output = T>
+        + Mul<Output = T>,
+{
+    if x == 0 {
+        return T::default();
+    }
+    let r = x.integer_square_root();
+    let sieve_limit = ((x as f64).powf(2.0 / 3.0) as u64).clamp(r, x);
+    // small[v] is the sum up to v, large[m] the sum up to x / m
+    let mut small = vec![T::default(); r as usize + 1];
+    let mut large = vec![T::default(); r as usize + 1];
+    let mut sum = T::default();
+    for_each_value(&(1..sieve_limit + 1), f, |n, value| {
+        sum = sum + T::from(value);
+        if n <= r {
+            small[n as usize] = sum;
+        }
+        let m = x / n;
+        if m <= r && x / m == n {
+            large[m as usize] = sum;
+        }
+    });
+    for m in (1..=r.min(x / (sieve_limit + 1))).rev() {
+        let v = x / m;
+        let mut subtracted = T::default();
+        let mut d = 2;
+        while d <= v {
+            let q = v / d;
+            let last = v / q;
+            // q = x / (m * d) exceeds √x only if m * d is below √x
+            let below = if q <= r {
+                small[q as usize]
+            } else {
+                large[(m * d) as usize]
+            };
+            subtracted = subtracted + T::from(last - d + 1) * below;
+            d = last + 1;
+        }
+        large[m as usize] = g(v) - subtracted;
+    }
+    large[1]
+}
+
+#[must_use]
+/// Mertens function M(x), the sum of the möbius function μ(n) over all n up to `x`
+///
+/// Sieves μ up to x^(2/3) and computes the rest recursively, taking O(x^(2/3)) time and O(√x) space.
+///
+/// # Example
+/// ```
+/// use facto::sieve::mertens;
+/// assert_eq!(mertens(100), 1);
+/// assert_eq!(mertens(1_000_000_000), -222);
+/// ```
+#[allow(clippy::cast_possible_truncation)]
+pub fn mertens(x: u64) -> i64 {
+    // |M(x)| never exceeds x, so the sum fits
+    summatory::<_, i128>(x, &Moebius, |_| 1) as i64
+}
+
+#[must_use]
+/// Summatory totient Φ(x), the sum of euler's totient φ(n) over all n up to `x`
+///
+/// Sieves φ up to x^(2/3) and computes the rest recursively like [`mertens`].
+///
+/// # Example
+/// ```
+/// use facto::sieve::totient_summatory;
+/// assert_eq!(totient_summatory(10), 32);
+/// assert_eq!(totient_summatory(1_000_000_000), 303_963_551_173_008_414);
+/// ```
+pub fn totient_summatory(x: u64) -> u128 {
+    summatory(x, &EulerPhi, |v| {
+        let v = u128::from(v);
+        v * (v + 1) / 2
+    })
+}
+
+#[must_use]
+/// Divisor summatory function D(x), the sum of the number of divisors τ(n) over all n up to `x`
+///
+/// Counts the lattice points under the hyperbola d * k = x by the dirichlet hyperbola method in O(√x) time.
+///
+/// # Example
+/// ```
+/// use facto::sieve::divisor_summatory;
+/// assert_eq!(divisor_summatory(10), 27);
+/// assert_eq!(divisor_summatory(1_000_000_000_000), 27_785_452_449_086);
+/// ```
+pub fn divisor_summatory(x: u64) -> u128 {
+    let r = x.integer_square_root();
+    let below: u128 = (1..=r).map(|k| u128::from(x / k)).sum();
+    2 * below - u128::from(r) * u128::from(r)
+}
+
+#[must_use]
+/// Sum of the divisor function σ(n) over all n up to `x`
+///
+/// Sums the divisors k over all d * k ≤ x by the dirichlet hyperbola method in O(√x) time.
+///
+/// # Example
+/// ```
+/// use facto::sieve::sigma_summatory;
+/// assert_eq!(sigma_summatory(10), 87);
+/// assert_eq!(sigma_summatory(1_000_000_000_000), 822_467_033_425_357_340_138_978);
+/// ```
+pub fn sigma_summatory(x: u64) -> u128 {
+    let triangle = |m: u64| {
+        let m = u128::from(m);
+        m * (m + 1) / 2
+    };
+    let r = x.integer_square_root();
+    (1..=r)
+        .map(|k| triangle(x / k) + u128::from(k) * u128::from(x / k))
+        .sum::<u128>()
+        - u128::from(r) * triangle(r)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{divisor_summatory, mertens, sigma_summatory, totient_summatory};
+    use crate::sieve::{multiplicative_table, EulerPhi, Moebius, Sigma, Tau};
+
+    #[test]
+    fn small_values() {
+        let moebius = multiplicative_table(1..3000, &Moebius);
+        let phi = multiplicative_table(1..3000, &EulerPhi);
+        let tau = multiplicative_table(1..3000, &Tau);
+        let sigma = multiplicative_table(1..3000, &Sigma(1));
+        for n in 0..3000usize {
+            let x = n as u64;
+            let m: i64 = moebius[..n].iter().map(|&v| i64::from(v)).sum();
+            assert_eq!(mertens(x), m, "M({x})");
+            let total: u64 = phi[..n].iter().sum();
+            assert_eq!(totient_summatory(x), u128::from(total), "Phi({x})");
+            let total: u64 = tau[..n].iter().sum();
+            assert_eq!(divisor_summatory(x), u128::from(total), "D({x})");
+            let total: u64 = sigma[..n].iter().sum();
+            assert_eq!(sigma_summatory(x), u128::from(total), "sigma({x})");
+        }
+    }
+
+    #[test]
+    fn powers_of_ten() {
+        let expected = [
+            (-1, 32, 27),
+            (1, 3044, 482),
+            (2, 304_192, 7069),
+            (-23, 30_397_486, 93668),
+            (-48, 3_039_650_754, 1_166_750),
+            (212, 303_963_552_392, 13_970_034),
+            (1037, 30_396_356_427_242, 162_725_364),
+            (1928, 3_039_635_516_365_908, 1_857_511_568),
+        ];
+        let mut x = 1;
+        for (m, phi, d) in expected {
+            x *= 10;
+            assert_eq!(mertens(x), m, "M({x})");
+            assert_eq!(totient_summatory(x), phi, "Phi({x})");
+            assert_eq!(divisor_summatory(x), d, "D({x})");
+        }
+        assert_eq!(sigma_summatory(1_000_000), 822_468_118_437);
+    }
+}
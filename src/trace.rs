@@ -0,0 +1,76 @@
+//! Spans of the optional `tracing` feature, expanding to nothing when it is disabled
+
+/// Enter a debug span called `$name`, which is left once `$guard` is dropped
+macro_rules! enter_span {
+    ($guard:ident, $name:literal $(, $($fields:tt)*)?) => {
+        #[cfg(feature = "tracing")]
+        let $guard = tracing::debug_span!($name $(, $($fields)*)?).entered();
+    };
+}
+
+/// Record `$value` as field `$field` of the span entered by [`enter_span`]
+macro_rules! record_span {
+    ($guard:ident, $field:literal, $value:expr) => {
+        #[cfg(feature = "tracing")]
+        {
+            $guard.record($field, $value);
+        }
+    };
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    use crate::{CertifiedFactorization, Factoring, PrimalityCertainty};
+
+    /// Collects the names of all spans being created
+    struct SpanNames(Arc<Mutex<Vec<&'static str>>>);
+
+    impl Subscriber for SpanNames {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let mut names = self.0.lock().unwrap();
+            names.push(span.metadata().name());
+            Id::from_u64(names.len() as u64)
+        }
+        fn record(&self, _span: &Id, _values: &Record<'_>) {}
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+        fn event(&self, _event: &Event<'_>) {}
+        fn enter(&self, _span: &Id) {}
+        fn exit(&self, _span: &Id) {}
+    }
+
+    fn span_names(f: impl FnOnce()) -> Vec<&'static str> {
+        let names = Arc::new(Mutex::new(vec![]));
+        tracing::subscriber::with_default(SpanNames(Arc::clone(&names)), f);
+        let result = names.lock().unwrap().clone();
+        result
+    }
+
+    #[test]
+    fn factoring_spans() {
+        let names = span_names(|| {
+            (4_294_967_279u128 * 4_294_967_291).factor();
+        });
+        for name in ["certified_factor", "pollard_loop", "pollard_rho"] {
+            assert!(names.contains(&name), "{} missing in {:?}", name, names);
+        }
+    }
+
+    #[test]
+    fn certification_spans() {
+        let names = span_names(|| {
+            assert!(18_446_744_073_709_551_629u128
+                .certified_prime_check(PrimalityCertainty::Guaranteed));
+        });
+        for name in ["delayed_lucas", "certified_factor"] {
+            assert!(names.contains(&name), "{} missing in {:?}", name, names);
+        }
+    }
+}
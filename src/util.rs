@@ -1,4 +1,5 @@
 use num_traits::PrimInt;
+use std::ops::Mul;
 
 #[allow(clippy::module_name_repetitions)]
 pub trait NumUtil {
@@ -6,6 +7,62 @@ pub trait NumUtil {
     fn integer_square_root(self) -> Self;
 }
 
+/// Number of bits needed to represent a number
+pub trait BitLength {
+    fn bit_length(&self) -> u32;
+}
+
+impl BitLength for u64 {
+    fn bit_length(&self) -> u32 {
+        Self::BITS - self.leading_zeros()
+    }
+}
+
+impl BitLength for u128 {
+    fn bit_length(&self) -> u32 {
+        Self::BITS - self.leading_zeros()
+    }
+}
+
+impl BitLength for rug::Integer {
+    fn bit_length(&self) -> u32 {
+        self.significant_bits()
+    }
+}
+
+/// `base` to the power of `exponent`, by square and multiply
+pub fn power<T>(mut base: T, mut exponent: u32) -> T
+where
+    T: Clone + From<u8> + Mul<Output = T>,
+{
+    let mut result = T::from(1);
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = result * base.clone();
+        }
+        exponent >>= 1;
+        if exponent > 0 {
+            base = base.clone() * base;
+        }
+    }
+    result
+}
+
+/// Inverse of `a` modulo the prime `p` below 2^32, by fermat's little theorem
+pub const fn inverse_mod(a: u64, p: u64) -> u64 {
+    let mut result = 1;
+    let mut base = a % p;
+    let mut exponent = p - 2;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = result * base % p;
+        }
+        base = base * base % p;
+        exponent >>= 1;
+    }
+    result
+}
+
 fn p_gcd<T>(mut u: T, mut v: T) -> T
 where
     T: PrimInt,
@@ -7,9 +7,12 @@
 #![allow(clippy::unseparated_literal_suffix)]
 
 use facto::{
-    CertifiedFactorization, EmptyFactoringEventSubscriptor, LucasCertificate, PrimalityCertainty,
+    primality::{LucasPrimality, MillerRabin},
+    CertificateElementKind, CertifiedFactorization, EmptyFactoringEventSubscriptor,
+    LucasCertificate, PrimalityCertainty,
 };
 use rug::{rand::RandState, Complete};
+use std::convert::TryFrom;
 
 fn get_rand_gen() -> RandState<'static> {
     let mut state = rug::rand::RandState::new();
@@ -25,10 +28,22 @@ fn get_rand_gen() -> RandState<'static> {
 
 fn check_certificate<T>(c: &LucasCertificate<T>)
 where
-    T: Into<rug::Integer> + Clone + Eq + Ord + std::fmt::Debug,
+    T: Into<rug::Integer>
+        + Clone
+        + Eq
+        + Ord
+        + std::fmt::Debug
+        + From<u8>
+        + MillerRabin
+        + LucasPrimality,
+    u64: TryFrom<T>,
 {
     for e in &c.elements {
         let e_n: rug::Integer = e.n.clone().into();
+        if e.kind == CertificateElementKind::MillerRabin {
+            assert!(e.verify(), "Miller-Rabin attestation of {} is invalid", e_n);
+            continue;
+        }
         if e_n == 2 {
             continue;
         }
@@ -75,17 +90,44 @@ where
         + Eq
         + Ord
         + std::fmt::Display
-        + std::ops::MulAssign,
+        + std::ops::MulAssign
+        + From<u8>
+        + MillerRabin
+        + LucasPrimality,
     rug::Integer: From<T>,
+    u64: TryFrom<T>,
+{
+    check_certified_factor_certainty(n, one, false);
+}
+
+fn check_certified_factor_certainty<T>(n: &T, one: T, compact: bool)
+where
+    T: std::fmt::Debug
+        + Clone
+        + CertifiedFactorization
+        + Default
+        + Eq
+        + Ord
+        + std::fmt::Display
+        + std::ops::MulAssign
+        + From<u8>
+        + MillerRabin
+        + LucasPrimality,
+    rug::Integer: From<T>,
+    u64: TryFrom<T>,
 {
     dbg!(&n);
 
     let mut c = LucasCertificate::default();
 
-    let v = n.clone().certified_factor(
-        PrimalityCertainty::Certified(&mut c),
-        EmptyFactoringEventSubscriptor {},
-    );
+    let certainty = if compact {
+        PrimalityCertainty::CertifiedCompact(&mut c)
+    } else {
+        PrimalityCertainty::Certified(&mut c)
+    };
+    let v = n
+        .clone()
+        .certified_factor(certainty, &mut EmptyFactoringEventSubscriptor {});
     let mut re = one;
     for f in v {
         re *= f.clone();
@@ -144,6 +186,20 @@ fn random_test_u128_smooth() {
     }
 }
 
+#[test]
+fn random_test_u128_compact() {
+    let mut state = get_rand_gen();
+    let threshold = rug::Integer::u_pow_u(2, 40).complete();
+
+    for _ in 0..15 {
+        let mut r = 1u128;
+        for _ in 0..3 {
+            r *= (threshold.clone().random_below(&mut state) + &threshold).to_u128_wrapping();
+        }
+        check_certified_factor_certainty(&r, 1, true);
+    }
+}
+
 #[test]
 #[ignore]
 fn random_test_rug() {
@@ -155,6 +211,32 @@ fn random_test_rug() {
     }
 }
 
+#[test]
+fn random_test_rug_compact() {
+    let mut state = get_rand_gen();
+    let threshold = rug::Integer::u_pow_u(2, 100).complete();
+
+    for _ in 0..3 {
+        let mut c = LucasCertificate::default();
+        let n = threshold.clone().random_below(&mut state) + &threshold;
+        let n = n.next_prime();
+        assert!(n
+            .clone()
+            .certified_prime_check(PrimalityCertainty::CertifiedCompact(&mut c)));
+        check_certificate(&c);
+        assert!(c
+            .elements
+            .iter()
+            .filter(|e| e.n > u64::MAX)
+            .all(|e| e.kind == CertificateElementKind::Lucas));
+        assert!(c
+            .elements
+            .iter()
+            .filter(|e| e.n <= u64::MAX && e.n != 2)
+            .all(|e| e.kind == CertificateElementKind::MillerRabin));
+    }
+}
+
 #[test]
 fn random_test_rug_smooth() {
     let mut state = get_rand_gen();